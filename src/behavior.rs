@@ -1,14 +1,35 @@
-use super::{Map, Monster, Position, Viewshed, RunState, WantsToMelee};
+use super::{Map, Monster, Position, Viewshed, RunState, WantsToMelee, CombatStats, Item, InBackpack,
+            ProvidesHealing, Ranged, InflictsDamage, AreaOfEffect, WantsToUseItem, WantsToPickupItem,
+            StatusEffects, StatusKind, status_system::random_direction, Door, WantsToOpenDoor};
 use specs::prelude::*;
 //use rltk::{field_of_view,Point,console};
-use rltk::{Point};
+use rltk::{Point, RandomNumberGenerator};
 
 pub struct MonsterAI {}
 
+// take one step along the path to the target, if there is one; a closed door in the way is handed back to be opened
+fn step_towards(map: &Map, doors: &ReadStorage<Door>, position: &mut Position, viewshed: &mut Viewshed, target: Point) -> Option<Entity> {
+    let path = rltk::a_star_search(
+        map.xy_idx(position.x, position.y) as i32,
+        map.xy_idx(target.x, target.y) as i32,
+        map,
+    );
+    if path.success && path.steps.len() > 1 {
+        if map.closed_doors[path.steps[1]] {
+            return map.tile_content[path.steps[1]].iter().find(|door| doors.get(**door).is_some()).copied();
+        }
+        let pos = map.size.idx_position(path.steps[1]);
+        position.x = pos.x;
+        position.y = pos.y;
+        viewshed.dirty = true;
+    }
+    None
+}
+
 impl<'a> System<'a> for MonsterAI {
     #[allow(clippy::type_complexity)]
     type SystemData = (
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         ReadExpect<'a, Point>,
         ReadExpect<'a, Entity>,
         ReadExpect<'a, RunState>,
@@ -17,11 +38,24 @@ impl<'a> System<'a> for MonsterAI {
         ReadStorage<'a, Monster>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, WantsToMelee>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, ProvidesHealing>,
+        ReadStorage<'a, Ranged>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, AreaOfEffect>,
+        WriteStorage<'a, WantsToUseItem>,
+        WriteStorage<'a, WantsToPickupItem>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Door>,
+        WriteStorage<'a, WantsToOpenDoor>,
+        WriteExpect<'a, RandomNumberGenerator>,
         // ReadStorage<'a, Name>
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map,
+        let (map,
             player_pos,
             player_entity,
             runstate,
@@ -29,33 +63,114 @@ impl<'a> System<'a> for MonsterAI {
             mut viewsheds,
             monsters,
             mut positions,
-            mut wants_to_melee) = data;
+            mut wants_to_melee,
+            combat_stats,
+            items,
+            backpack,
+            healing,
+            ranged,
+            inflict_damage,
+            aoe,
+            mut wants_to_use,
+            mut wants_to_pickup,
+            status_effects,
+            doors,
+            mut wants_to_open,
+            mut rng) = data;
 
-        for (entity, mut viewshed, _monster, mut position) in
-            (&entities, &mut viewsheds, &monsters, &mut positions).join()
+        for (entity, viewshed, _monster, position, stats) in
+            (&entities, &mut viewsheds, &monsters, &mut positions, &combat_stats).join()
         {
             if *runstate != RunState::MonsterTurn { return; }
 
-            if rltk::DistanceAlg::Pythagoras
-                    .distance2d(Point::new(position.x, position.y), *player_pos)
-                    < 1.5
-            {
+            // sleepers lose their turn, the confused wander at random
+            let statuses = status_effects.get(entity);
+            if statuses.is_some_and(|s| s.has(StatusKind::Sleep)) { continue; }
+            if statuses.is_some_and(|s| s.has(StatusKind::Confusion)) {
+                let (dx, dy) = random_direction(&mut rng);
+                let (nx, ny) = (position.x + dx, position.y + dy);
+                if !map.blocked[map.xy_idx(nx, ny)] {
+                    position.x = nx;
+                    position.y = ny;
+                    viewshed.dirty = true;
+                }
+                continue;
+            }
+
+            // half health or worse sends a monster looking for a potion
+            let wounded = stats.hp * 2 <= stats.max_hp;
+            let distance = rltk::DistanceAlg::Pythagoras
+                .distance2d(Point::new(position.x, position.y), *player_pos);
+            let can_see_player = viewshed.visible_tiles.contains(&*player_pos);
+            let carried : Vec<Entity> = (&entities, &backpack).join()
+                .filter(|(_item, pack)| pack.owner == entity)
+                .map(|(item, _pack)| item)
+                .collect();
+
+            // drink a potion it is already carrying
+            if wounded {
+                if let Some(potion) = carried.iter().find(|item| healing.get(**item).is_some()) {
+                    wants_to_use.insert(entity, WantsToUseItem{ item: *potion, target: None }).expect("unable to insert intent");
+                    continue;
+                }
+            }
+
+            // fire a damaging scroll at the player, as long as the blast won't reach back
+            if can_see_player {
+                let scroll = carried.iter().find(|item| {
+                    inflict_damage.get(**item).is_some()
+                        && ranged.get(**item).is_some_and(|r| distance <= r.range as f32)
+                        && aoe.get(**item).is_none_or(|area| distance > area.radius as f32)
+                });
+                if let Some(scroll) = scroll {
+                    wants_to_use.insert(entity, WantsToUseItem{ item: *scroll, target: Some(*player_pos) }).expect("unable to insert intent");
+                    continue;
+                }
+            }
+
+            // pick up anything underfoot it knows how to use
+            let useful = |item: &Entity| items.get(*item).is_some()
+                && (healing.get(*item).is_some() || (inflict_damage.get(*item).is_some() && ranged.get(*item).is_some()));
+            if distance >= 1.5 {
+                let idx = map.xy_idx(position.x, position.y);
+                if let Some(item) = map.tile_content[idx].iter().find(|item| useful(item)) {
+                    wants_to_pickup.insert(entity, WantsToPickupItem{ collected_by: entity, item: *item }).expect("unable to insert intent");
+                    continue;
+                }
+            }
+
+            if distance < 1.5 {
                 //console::log(&format!("{} shouts insults", name.name));
                 wants_to_melee.insert(entity, WantsToMelee{ target: *player_entity }).expect("unable to insert attack");
+                continue;
             }
-            else if viewshed.visible_tiles.contains(&*player_pos) {
-                let path = rltk::a_star_search(
-                    map.xy_idx(position.x, position.y) as i32,
-                    map.xy_idx(player_pos.x, player_pos.y) as i32,
-                    &mut *map,
-                );
-                if path.success && path.steps.len() > 1 {
-                    let pos = map.size.idx_position(path.steps[1]);
-                    position.x = pos.x;
-                    position.y = pos.y;
-                    viewshed.dirty = true;
-                    //console::log(&format!("{} runs towards you", name.name));
+
+            // head for the nearest potion in sight
+            if wounded {
+                let here = Point::new(position.x, position.y);
+                let potion = viewshed.visible_tiles.iter()
+                    .filter(|tile| map.tile_content[map.xy_idx(tile.x, tile.y)].iter()
+                        .any(|item| items.get(*item).is_some() && healing.get(*item).is_some()))
+                    // the visible tiles come out of a hash set, so ties go to the topmost, then leftmost, potion
+                    .min_by(|a, b| {
+                        let da = rltk::DistanceAlg::Pythagoras.distance2d(here, **a);
+                        let db = rltk::DistanceAlg::Pythagoras.distance2d(here, **b);
+                        da.total_cmp(&db).then(a.y.cmp(&b.y)).then(a.x.cmp(&b.x))
+                    })
+                    .copied();
+                if let Some(potion) = potion {
+                    if let Some(door) = step_towards(&map, &doors, position, viewshed, potion) {
+                        wants_to_open.insert(entity, WantsToOpenDoor{ door }).expect("unable to insert intent");
+                    }
+                    continue;
+                }
+            }
+
+            if can_see_player {
+                if let Some(door) = step_towards(&map, &doors, position, viewshed, *player_pos) {
+                    wants_to_open.insert(entity, WantsToOpenDoor{ door }).expect("unable to insert intent");
                 }
+                //console::log(&format!("{} runs towards you", name.name));
             }
         }
     }
@@ -1,5 +1,9 @@
-use rltk::{Point, RGB};
+use super::{GameLog, Map};
+use rltk::{Point, RandomNumberGenerator, RGB};
+use serde::{Deserialize, Serialize};
+use specs::error::NoError;
 use specs::prelude::*;
+use specs::saveload::{ConvertSaveload, Marker};
 use specs_derive::*;
 use std::cmp::{max, min, Ordering};
 use std::ops::{Add, Sub};
@@ -10,7 +14,8 @@ pub fn range<T: std::cmp::Ord>(l: T, v: T, u: T) -> T {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Default, Debug, Component, Copy, Clone, PartialEq, PartialOrd)]
+#[allow(clippy::derive_ord_xor_partial_ord)]
+#[derive(Default, Debug, Component, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -59,7 +64,7 @@ impl Ord for Position {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct CombatStats {
     pub max_hp: i32,
     pub hp: i32,
@@ -68,24 +73,31 @@ pub struct CombatStats {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, ConvertSaveload)]
 pub struct WantsToMelee {
     pub target: Entity,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+// each hit remembers who dealt it, so a kill can be credited;
+// damage never outlives the turn it was dealt in, so it isn't saved
+#[derive(Component, Debug, Clone)]
 pub struct SufferDamage {
-    pub amount: Vec<i32>,
+    pub amount: Vec<(i32, Option<Entity>)>,
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
+    pub fn new_damage(
+        store: &mut WriteStorage<SufferDamage>,
+        victim: Entity,
+        amount: i32,
+        from: Option<Entity>,
+    ) {
         if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
+            suffering.amount.push((amount, from));
         } else {
             let dmg = SufferDamage {
-                amount: vec![amount],
+                amount: vec![(amount, from)],
             };
             store.insert(victim, dmg).expect("unable to insert damage");
         }
@@ -93,7 +105,42 @@ impl SufferDamage {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component)]
+// left on anything whose hp reached zero, naming whoever landed the last hit
+#[derive(Component, Debug, Clone)]
+pub struct Slain {
+    pub killer: Option<Entity>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+impl Experience {
+    // total xp needed to reach the next level: 100, 300, 600, ...
+    pub fn next_level_xp(&self) -> i32 {
+        100 * self.level * (self.level + 1) / 2
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// how the player's run has gone, for the game over screen
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerRecord {
+    pub turns: i32,
+    pub kills: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct GrantsXp {
+    pub amount: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Renderable {
     pub glyph: rltk::FontCharType,
     pub fg: RGB,
@@ -102,7 +149,7 @@ pub struct Renderable {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component)]
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Viewshed {
     pub visible_tiles: Vec<rltk::Point>,
     pub range: i32,
@@ -110,74 +157,267 @@ pub struct Viewshed {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Name {
     pub name: String,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+// a closed door blocks the tile and the view through it; the indexing system reads `open` into the map
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Door {
+    pub open: bool,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Player {}
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Monster {}
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Item {}
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Consumable {}
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct ProvidesHealing {
     pub amount: i32,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
-pub struct Potion {
-    pub heal_amount: i32,
-}
-
-// ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, ConvertSaveload)]
 pub struct InBackpack {
     pub owner: Entity,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, ConvertSaveload)]
 pub struct WantsToPickupItem {
     pub collected_by: Entity,
     pub item: Entity,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
-pub struct WantsToConsumeItem {
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToUseItem {
+    pub item: Entity,
+    pub target: Option<Point>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToDropItem {
     pub item: Entity,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToOpenDoor {
+    pub door: Entity,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToCloseDoor {
+    pub door: Entity,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Ranged {
     pub range: i32,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct InflictsDamage {
     pub amount: i32,
 }
 
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct AreaOfEffect {
+    pub radius: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// items that leave a lasting effect on whoever they are used on
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Confusion {
+    pub turns: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Sleep {
+    pub turns: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Poison {
+    pub turns: i32,
+    pub damage: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Regeneration {
+    pub turns: i32,
+    pub amount: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum StatusKind {
+    Confusion,
+    Sleep,
+    Poison,
+    Regeneration,
+}
+
+impl StatusKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusKind::Confusion => "confused",
+            StatusKind::Sleep => "asleep",
+            StatusKind::Poison => "poisoned",
+            StatusKind::Regeneration => "regenerating",
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// potency is the damage or healing per turn, where the effect has one
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns: i32,
+    pub potency: i32,
+    // who caused it, for credit if it kills; not kept across a save
+    #[serde(skip)]
+    pub source: Option<Entity>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    // a second dose of the same effect extends it rather than stacking
+    pub fn apply(store: &mut WriteStorage<StatusEffects>, victim: Entity, effect: StatusEffect) {
+        if let Some(statuses) = store.get_mut(victim) {
+            match statuses.effects.iter_mut().find(|e| e.kind == effect.kind) {
+                Some(existing) => {
+                    existing.turns = i32::max(existing.turns, effect.turns);
+                    existing.potency = i32::max(existing.potency, effect.potency);
+                    existing.source = effect.source;
+                }
+                None => statuses.effects.push(effect),
+            }
+        } else {
+            let statuses = StatusEffects {
+                effects: vec![effect],
+            };
+            store
+                .insert(victim, statuses)
+                .expect("Unable to insert status effect");
+        }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    Melee,
+    Shield,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Equippable {
+    pub slot: EquipmentSlot,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MeleePowerBonus {
+    pub power: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct DefenseBonus {
+    pub defense: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToRemoveItem {
+    pub item: Entity,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// marker for entities that get written to the save file
+pub struct SerializeMe;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the seed the game was started from; everything random is drawn from the generator it seeded
+#[derive(Serialize, Deserialize, Copy, Clone)]
+pub struct GameSeed {
+    pub seed: u64,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// carries resources through the save file on a temporary entity
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct SerializationHelper {
+    pub map: Map,
+    pub log: GameLog,
+    pub rng: RandomNumberGenerator,
+    pub seed: GameSeed,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone)]
+pub enum MainMenuSelection {
+    NewGame,
+    Continue,
+    Quit,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// things the player keeps doing, a turn at a time, until something gets in the way
+#[derive(PartialEq, Copy, Clone)]
+pub enum Activity {
+    Rest,
+    Explore,
+}
+
 // ------------------------------------------------------------------------------------------------------------------ //
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
@@ -186,5 +426,28 @@ pub enum RunState {
     PlayerTurn,
     MonsterTurn,
     ShowInventory,
-    ShowTargeting { range: i32, item: Entity },
+    ShowDropItem,
+    ShowTargeting {
+        range: i32,
+        item: Entity,
+        cursor: Point,
+    },
+    NextLevel,
+    SaveGame,
+    LoadGame,
+    GameOver,
+    MainMenu {
+        selected: MainMenuSelection,
+        seed: Option<u64>,
+        load_failed: bool,
+    },
+    ShowLog {
+        page: usize,
+    },
+    Activity(Activity),
+    // a debug view of the builder's snapshots for a freshly dug level, shown with --show-mapgen
+    MapGeneration {
+        frame: usize,
+        paused: bool,
+    },
 }
@@ -1,19 +1,33 @@
 use specs::prelude::*;
+use rltk::RGB;
 //use rltk:–:{console};
-use super::{CombatStats,SufferDamage,Name,Player,GameLog};
+use super::{CombatStats,SufferDamage,Name,Player,GameLog,LogCategory,InBackpack,Position,Slain,Experience,GrantsXp,PlayerRecord};
+
+// what each new level adds to the player
+const LEVEL_HP_GAIN : i32 = 10;
+const LEVEL_POWER_GAIN : i32 = 1;
 
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
-    type SystemData = ( WriteStorage<'a, CombatStats>,
-                        WriteStorage<'a, SufferDamage>
+    type SystemData = ( Entities<'a>,
+                        WriteStorage<'a, CombatStats>,
+                        WriteStorage<'a, SufferDamage>,
+                        WriteStorage<'a, Slain>
                     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut statses, mut damages) = data;
+        let (entities, mut statses, mut damages, mut slain) = data;
+
+        for (entity, stats, damage) in (&entities, &mut statses, &damages).join() {
+            let was_alive = stats.hp > 0;
+            stats.hp = i32::max(0, stats.hp - damage.amount.iter().map(|(amount, _from)| amount).sum::<i32>());
 
-        for (mut stats, damage) in (&mut statses, &damages).join() {
-            stats.hp = i32::max(0, stats.hp - damage.amount.iter().sum::<i32>());
+            // the last hit with a known source gets the credit
+            if was_alive && stats.hp == 0 {
+                let killer = damage.amount.iter().rev().find_map(|(_amount, from)| *from);
+                slain.insert(entity, Slain{ killer }).expect("Unable to insert slain");
+            }
         }
 
         damages.clear();
@@ -35,15 +49,73 @@ pub fn delete_the_dead(ecs: &mut World) {
                 let player = players.get(entity);
                 match player {
                     None => {
-                        log.entries.push(format!("{} has died!", &target_name.name));
+                        log.add(LogCategory::Combat, format!("{} has died!", &target_name.name));
                         dead.push(entity);
                     }
-                    Some(_) => log.entries.push("You have died!".to_string())
+                    Some(_) => log.add_coloured(LogCategory::Combat, RGB::named(rltk::RED), "You have died!")
                 }
             }
         }
     }
 
+    // killers earn the xp the dead were worth
+    {
+        let slain = ecs.read_storage::<Slain>();
+        let grants = ecs.read_storage::<GrantsXp>();
+        let names = ecs.read_storage::<Name>();
+        let player_entity = *ecs.fetch::<Entity>();
+        let mut experience = ecs.write_storage::<Experience>();
+        let mut records = ecs.write_storage::<PlayerRecord>();
+        let mut combat_stats = ecs.write_storage::<CombatStats>();
+        let mut log = ecs.write_resource::<GameLog>();
+        for victim in dead.iter() {
+            let killer = match slain.get(*victim).and_then(|s| s.killer) {
+                Some(killer) => killer,
+                None => continue
+            };
+            if let Some(record) = records.get_mut(killer) {
+                record.kills += 1;
+            }
+            let (xp, exp) = match (grants.get(*victim), experience.get_mut(killer)) {
+                (Some(xp), Some(exp)) => (xp.amount, exp),
+                _ => continue
+            };
+            exp.xp += xp;
+            if killer == player_entity {
+                log.add(LogCategory::Combat, format!("You gain {} xp for the {}.", xp, &names.get(*victim).unwrap().name));
+            }
+
+            while exp.xp >= exp.next_level_xp() {
+                exp.level += 1;
+                if let Some(stats) = combat_stats.get_mut(killer) {
+                    stats.max_hp += LEVEL_HP_GAIN;
+                    stats.hp += LEVEL_HP_GAIN;
+                    stats.power += LEVEL_POWER_GAIN;
+                    if killer == player_entity {
+                        log.add_coloured(LogCategory::Combat, RGB::named(rltk::GOLD), format!("Welcome to level {}! Max hp is now {} and power {}.", exp.level, stats.max_hp, stats.power));
+                    }
+                }
+            }
+        }
+    }
+
+    // whatever the dead were carrying falls to the floor where they stood
+    {
+        let entities = ecs.entities();
+        let mut backpack = ecs.write_storage::<InBackpack>();
+        let mut positions = ecs.write_storage::<Position>();
+        let carried : Vec<(Entity, Entity)> = (&entities, &backpack).join()
+            .filter(|(_item, pack)| dead.contains(&pack.owner))
+            .map(|(item, pack)| (item, pack.owner))
+            .collect();
+        for (item, owner) in carried {
+            if let Some(pos) = positions.get(owner).copied() {
+                positions.insert(item, pos).expect("Unable to insert position");
+            }
+            backpack.remove(item);
+        }
+    }
+
     for victim in dead {
         ecs.delete_entity(victim).expect("Unable to delete dead");
     }
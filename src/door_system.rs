@@ -0,0 +1,93 @@
+use super::{
+    spawner::{DOOR_CLOSED_GLYPH, DOOR_OPEN_GLYPH},
+    Door, GameLog, LogCategory, Map, Name, Position, Renderable, Viewshed, WantsToCloseDoor,
+    WantsToOpenDoor,
+};
+use specs::prelude::*;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub struct DoorSystem {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// runs before the map is indexed, so a door opened this turn no longer blocks anything that moves after it
+impl<'a> System<'a> for DoorSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        WriteStorage<'a, WantsToOpenDoor>,
+        WriteStorage<'a, WantsToCloseDoor>,
+        WriteStorage<'a, Door>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            map,
+            mut gamelog,
+            entities,
+            mut wants_open,
+            mut wants_close,
+            mut doors,
+            mut renderables,
+            mut viewsheds,
+            positions,
+            names,
+        ) = data;
+
+        let mut changed = false;
+        for (entity, intent) in (&entities, &wants_open).join() {
+            let door = match doors.get_mut(intent.door) {
+                Some(door) if !door.open => door,
+                _ => continue,
+            };
+            door.open = true;
+            if let Some(render) = renderables.get_mut(intent.door) {
+                render.glyph = rltk::to_cp437(DOOR_OPEN_GLYPH);
+            }
+            changed = true;
+
+            if entity == *player_entity {
+                gamelog.add(LogCategory::System, "You open the door.");
+            } else if positions
+                .get(intent.door)
+                .is_some_and(|pos| map.visible_tiles[map.position_idx(*pos)])
+            {
+                let name = names.get(entity).map_or("Something", |n| n.name.as_str());
+                gamelog.add(LogCategory::System, format!("{} opens a door.", name));
+            }
+        }
+
+        for (entity, intent) in (&entities, &wants_close).join() {
+            let door = match doors.get_mut(intent.door) {
+                Some(door) if door.open => door,
+                _ => continue,
+            };
+            door.open = false;
+            if let Some(render) = renderables.get_mut(intent.door) {
+                render.glyph = rltk::to_cp437(DOOR_CLOSED_GLYPH);
+            }
+            changed = true;
+
+            if entity == *player_entity {
+                gamelog.add(LogCategory::System, "You close the door.");
+            }
+        }
+
+        // a door changes what everyone can see, not just whoever moved it
+        if changed {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+
+        wants_open.clear();
+        wants_close.clear();
+    }
+}
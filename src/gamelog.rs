@@ -1,6 +1,73 @@
+use rltk::RGB;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::OnceLock;
 
+// how many entries are kept when --log-history isn't given
+pub const DEFAULT_HISTORY_LIMIT: usize = 500;
+
+static HISTORY_LIMIT: OnceLock<usize> = OnceLock::new();
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// set once at startup; every log made after that keeps at most this many entries
+pub fn set_history_limit(limit: usize) {
+    let _ = HISTORY_LIMIT.set(usize::max(1, limit));
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn history_limit() -> usize {
+    *HISTORY_LIMIT.get().unwrap_or(&DEFAULT_HISTORY_LIMIT)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum LogCategory {
+    Combat,
+    Item,
+    System,
+}
+
+impl LogCategory {
+    pub fn colour(self) -> RGB {
+        match self {
+            LogCategory::Combat => RGB::named(rltk::LIGHT_SALMON),
+            LogCategory::Item => RGB::named(rltk::LIGHT_BLUE),
+            LogCategory::System => RGB::named(rltk::LIGHT_GRAY),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogEntry {
+    pub text: String,
+    pub category: LogCategory,
+    pub colour: RGB,
+    pub turn: i32,
+}
 
 // ------------------------------------------------------------------------------------------------------------------ //
+// entries are stamped with the turn the log was last told about, and the oldest fall off past the history limit
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct GameLog {
-    pub entries: Vec<String>
-}
\ No newline at end of file
+    pub entries: VecDeque<LogEntry>,
+    pub turn: i32,
+}
+
+impl GameLog {
+    pub fn add<S: Into<String>>(&mut self, category: LogCategory, text: S) {
+        self.add_coloured(category, category.colour(), text);
+    }
+
+    pub fn add_coloured<S: Into<String>>(&mut self, category: LogCategory, colour: RGB, text: S) {
+        self.entries.push_back(LogEntry {
+            text: text.into(),
+            category,
+            colour,
+            turn: self.turn,
+        });
+        while self.entries.len() > history_limit() {
+            self.entries.pop_front();
+        }
+    }
+}
@@ -1,26 +1,31 @@
-use super::{CombatStats, GameLog, InBackpack, Name, Player, Position, State, Viewport, Viewshed};
-use rltk::{Point, Rltk, VirtualKeyCode, RGB};
+use super::{
+    gamelog::LogEntry,
+    inventory_system::{blast_tiles, inventory_items},
+    keybindings::{self, MenuAction},
+    map_builders::MapGenHistory,
+    player::PlayerCommand,
+    saveload_system, AreaOfEffect, CombatStats, Experience, GameLog, GameSeed, MainMenuSelection,
+    Map, Name, Player, PlayerRecord, Position, RunState, Slain, State, StatusEffects, StatusKind,
+    Viewport, Viewshed, DEFAULT_SEED,
+};
+use rltk::{Algorithm2D, Point, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn draw_inventory(world: &World, ctx: &mut Rltk, viewport: &Viewport) {
+fn draw_inventory(world: &World, ctx: &mut Rltk, viewport: &Viewport, title: &str) {
     let player_entity = world.fetch::<Entity>();
     let names = world.read_storage::<Name>();
-    let backpack = world.read_storage::<InBackpack>();
 
-    let inventory: Vec<(&InBackpack, &Name)> = (&backpack, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity)
-        .collect();
+    let inventory = inventory_items(world, *player_entity);
 
     let count = inventory.len() as i32;
 
-    let mut y = (viewport.map_height / 2 - count / 2) as i32;
+    let y = viewport.map_height / 2 - count / 2;
     ctx.draw_box(
         15,
         y - 2,
         31,
-        (count + 3) as i32,
+        count + 3,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
     );
@@ -29,18 +34,18 @@ fn draw_inventory(world: &World, ctx: &mut Rltk, viewport: &Viewport) {
         y - 2,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Inventory",
+        title,
     );
     ctx.print_color(
         18,
-        y + count as i32 + 1,
+        y + count + 1,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
         "ESCAPE to cancel",
     );
 
-    let mut j = 0;
-    for (_pack, name) in inventory {
+    for (j, (item, equipped)) in inventory.into_iter().enumerate() {
+        let y = y + j as i32;
         ctx.set(
             17,
             y,
@@ -63,14 +68,24 @@ fn draw_inventory(world: &World, ctx: &mut Rltk, viewport: &Viewport) {
             rltk::to_cp437(')'),
         );
 
-        ctx.print(21, y, &name.name.to_string());
-        y += 1;
-        j += 1;
+        // choosing an equipped item takes it off, so mark what's being worn
+        let name = &names.get(item).unwrap().name;
+        if equipped {
+            ctx.print_color(
+                21,
+                y,
+                RGB::named(rltk::GREEN),
+                RGB::named(rltk::BLACK),
+                format!("{} (equipped)", name),
+            );
+        } else {
+            ctx.print(21, y, name);
+        }
     }
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-pub fn draw_ui(world: &World, ctx: &mut Rltk, viewport: &Viewport, show_inventory: bool) {
+pub fn draw_ui(world: &World, ctx: &mut Rltk, viewport: &Viewport, item_menu: Option<&str>) {
     // draw border
     ctx.draw_box(
         0,
@@ -81,44 +96,176 @@ pub fn draw_ui(world: &World, ctx: &mut Rltk, viewport: &Viewport, show_inventor
         RGB::named(rltk::BLACK),
     );
 
-    // draw player health
+    // draw dungeon depth
+    let depth = format!("Depth: {}", world.fetch::<Map>().depth);
+    ctx.print_color(
+        2,
+        viewport.map_height,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        &depth,
+    );
+
+    // draw player level, health and experience
     let combat_stats = world.read_storage::<CombatStats>();
+    let experience = world.read_storage::<Experience>();
     let players = world.read_storage::<Player>();
-    for (index, info) in (&players, &combat_stats).join().enumerate() {
-        let (_player, stats) = info;
-        let health = format!("HP: {} / {}", stats.hp, stats.max_hp);
-        let vert_offset = (index * 1) as i32;
+    for (index, info) in (&players, &combat_stats, &experience).join().enumerate() {
+        let (_player, stats, exp) = info;
+        let vert_offset = index as i32;
         ctx.print_color(
             12,
             viewport.map_height + vert_offset,
             RGB::named(rltk::YELLOW),
             RGB::named(rltk::BLACK),
+            format!("Level: {}", exp.level),
+        );
+
+        let health = format!("HP: {} / {}", stats.hp, stats.max_hp);
+        ctx.print_color(
+            23,
+            viewport.map_height + vert_offset,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
             &health,
         );
 
         ctx.draw_bar_horizontal(
-            28,
+            37,
             viewport.map_height + vert_offset,
-            51,
+            26,
             stats.hp,
             stats.max_hp,
             RGB::named(rltk::RED),
             RGB::named(rltk::BLACK),
         );
+
+        ctx.print_color(
+            65,
+            viewport.map_height + vert_offset,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            format!("XP: {}/{}", exp.xp, exp.next_level_xp()),
+        );
+    }
+
+    // draw the seed on the bottom border, so a run can be shared or replayed
+    let seed = format!("Seed: {}", world.fetch::<GameSeed>().seed);
+    ctx.print_color(
+        viewport.map_width - 2 - seed.len() as i32,
+        viewport.map_height + viewport.log_height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        &seed,
+    );
+
+    // draw the player's lasting effects on the line above the log
+    let player_entity = world.fetch::<Entity>();
+    if let Some(statuses) = world.read_storage::<StatusEffects>().get(*player_entity) {
+        let mut x = 2;
+        for effect in statuses.effects.iter() {
+            let colour = match effect.kind {
+                StatusKind::Confusion => RGB::named(rltk::MAGENTA),
+                StatusKind::Sleep => RGB::named(rltk::LIGHT_BLUE),
+                StatusKind::Poison => RGB::named(rltk::GREEN),
+                StatusKind::Regeneration => RGB::named(rltk::CYAN),
+            };
+            let text = format!("{} ({})", effect.kind.label(), effect.turns);
+            ctx.print_color(
+                x,
+                viewport.map_height + 1,
+                colour,
+                RGB::named(rltk::BLACK),
+                &text,
+            );
+            x += text.len() as i32 + 2;
+        }
     }
 
     // draw log
     let log = world.fetch::<GameLog>();
+    let log_key = keybindings::get()
+        .key_for(PlayerCommand::RunState(RunState::ShowLog { page: 0 }))
+        .map_or("?", keybindings::key_name);
     let y = viewport.map_height + 2;
-    for (i, s) in log.entries.iter().rev().enumerate() {
+    for (i, entry) in log.entries.iter().rev().enumerate() {
         let yoff = y + (i as i32);
         if yoff < viewport.map_height + viewport.log_height - 1 {
-            ctx.print(2, yoff, s);
+            draw_log_entry(ctx, 2, yoff, entry);
         }
     }
+    ctx.print_color(
+        2,
+        viewport.map_height + viewport.log_height - 1,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!("{}: message history", log_key),
+    );
+
+    draw_tooltips(world, ctx, viewport);
 
-    if show_inventory {
-        draw_inventory(world, ctx, viewport);
+    if let Some(title) = item_menu {
+        draw_inventory(world, ctx, viewport, title);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// names whatever the player can see under the mouse; unseen tiles stay silent so nothing hidden is given away
+fn draw_tooltips(world: &World, ctx: &mut Rltk, viewport: &Viewport) {
+    let (mouse_x, mouse_y) = ctx.mouse_pos();
+    let Some(tile) = viewport
+        .camera(world)
+        .to_world(Point::new(mouse_x, mouse_y))
+    else {
+        return;
+    };
+    let map = world.fetch::<Map>();
+    if !map.in_bounds(tile) || !map.visible_tiles[map.xy_idx(tile.x, tile.y)] {
+        return;
+    }
+
+    let names = world.read_storage::<Name>();
+    let positions = world.read_storage::<Position>();
+    let combat_stats = world.read_storage::<CombatStats>();
+    let lines: Vec<String> = (&names, &positions, combat_stats.maybe())
+        .join()
+        .filter(|(_name, pos, _stats)| pos.x == tile.x && pos.y == tile.y)
+        .map(|(name, _pos, stats)| match stats {
+            Some(stats) => format!("{} (HP {}/{})", name.name, stats.hp, stats.max_hp),
+            None => name.name.clone(),
+        })
+        .collect();
+    if lines.is_empty() {
+        return;
+    }
+
+    // the box sits right of the cursor unless that would run off the map, then it flips to the left,
+    // stopping at the left edge if the names are too long to fit there either
+    let width = lines.iter().map(|line| line.len()).max().unwrap() as i32 + 2;
+    let height = lines.len() as i32 + 2;
+    let x = if mouse_x + 2 + width <= viewport.map_width {
+        mouse_x + 2
+    } else {
+        i32::max(0, mouse_x - 1 - width)
+    };
+    let y = i32::max(0, i32::min(mouse_y - 1, viewport.map_height - height));
+
+    ctx.draw_box(
+        x,
+        y,
+        width - 1,
+        height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::DARK_GREY),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print_color(
+            x + 1,
+            y + 1 + i as i32,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::DARK_GREY),
+            line,
+        );
     }
 }
 
@@ -135,43 +282,54 @@ pub enum ItemMenuResult {
 pub enum ItemTargetingResult {
     Cancel,
     NoResponse,
+    MoveCursor(Point),
     Targeted(Point),
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-pub fn menu_inventory(gs: &mut State, ctx: &mut Rltk) -> ItemMenuResult {
-    let world = &gs.ecs;
+fn menu_select_item(world: &World, ctx: &mut Rltk) -> ItemMenuResult {
     let player_entity = world.fetch::<Entity>();
-    let backpack = world.read_storage::<InBackpack>();
-    let entities = world.entities();
 
-    let inventory: Vec<(Entity, &InBackpack)> = (&entities, &backpack)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
-        .collect();
+    let inventory = inventory_items(world, *player_entity);
     let count = inventory.len() as i32;
 
     match ctx.key {
         None => ItemMenuResult::NoResponse,
-        Some(key) => match key {
-            VirtualKeyCode::Escape => ItemMenuResult::Cancel,
-            _ => {
-                let selection = rltk::letter_to_option(key);
-                if selection > -1 && selection < count {
-                    ItemMenuResult::Selected(inventory[selection as usize].0)
-                } else {
-                    ItemMenuResult::NoResponse
-                }
+        Some(key) if keybindings::get().is(key, MenuAction::Cancel) => ItemMenuResult::Cancel,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < count {
+                ItemMenuResult::Selected(inventory[selection as usize].0)
+            } else {
+                ItemMenuResult::NoResponse
             }
-        },
+        }
     }
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32) -> ItemTargetingResult {
+pub fn menu_inventory(gs: &mut State, ctx: &mut Rltk) -> ItemMenuResult {
+    menu_select_item(&gs.ecs, ctx)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn menu_drop_item(gs: &mut State, ctx: &mut Rltk) -> ItemMenuResult {
+    menu_select_item(&gs.ecs, ctx)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn ranged_target(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    item: Entity,
+    range: i32,
+    cursor: Point,
+) -> ItemTargetingResult {
     let player_entity = gs.ecs.fetch::<Entity>();
     let player_pos = gs.ecs.fetch::<Point>();
+    let map = gs.ecs.fetch::<Map>();
     let viewsheds = gs.ecs.read_storage::<Viewshed>();
+    let aoe = gs.ecs.read_storage::<AreaOfEffect>();
 
     ctx.print_color(
         5,
@@ -181,7 +339,13 @@ pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32) -> ItemTargetin
         "Select Target:",
     );
 
-    // highlight available target cells
+    // highlight available target cells; everything below works in map tiles and only goes to the screen to draw
+    let camera = gs.viewport.camera(&gs.ecs);
+    let highlight = |ctx: &mut Rltk, tile: Point, colour: (u8, u8, u8)| {
+        if let Some(screen) = camera.to_screen(tile) {
+            ctx.set_bg(screen.x, screen.y, RGB::named(colour));
+        }
+    };
     let mut available_cells = Vec::new();
     let visible = viewsheds.get(*player_entity);
     if let Some(visible) = visible {
@@ -189,15 +353,508 @@ pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32) -> ItemTargetin
         for visible_p in visible.visible_tiles.iter() {
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *visible_p);
             if distance <= range as f32 {
-                ctx.set_bg(visible_p.x, visible_p.y, RGB::named(rltk::BLUE));
-                available_cells.push(visible_p);
+                highlight(ctx, *visible_p, rltk::BLUE);
+                available_cells.push(*visible_p);
             }
         }
     } else {
         return ItemTargetingResult::Cancel;
     }
 
+    // the mouse takes over from the keyboard cursor while it hovers a target cell
+    let (mouse_x, mouse_y) = ctx.mouse_pos();
+    let mouse_pos = camera.to_world(Point::new(mouse_x, mouse_y));
+    let mouse_target = mouse_pos.filter(|pos| available_cells.contains(pos));
+
+    // draw the blast footprint under the active cursor
+    if let Some(area) = aoe.get(item) {
+        let centre = mouse_target.unwrap_or(cursor);
+        if available_cells.contains(&centre) {
+            for tile in blast_tiles(&map, centre, area.radius).iter() {
+                highlight(ctx, *tile, rltk::ORANGE);
+            }
+        }
+    }
+
+    // draw keyboard cursor
+    if available_cells.contains(&cursor) {
+        highlight(ctx, cursor, rltk::CYAN);
+    }
+
     // draw mouse cursor
+    if let Some(target) = mouse_target {
+        ctx.set_bg(mouse_x, mouse_y, RGB::named(rltk::CYAN));
+        if ctx.left_click {
+            return ItemTargetingResult::Targeted(target);
+        }
+    } else {
+        ctx.set_bg(mouse_x, mouse_y, RGB::named(rltk::RED));
+        if ctx.left_click {
+            return ItemTargetingResult::Cancel;
+        }
+    }
+
+    match ctx.key {
+        None => ItemTargetingResult::NoResponse,
+        Some(key) => {
+            let keys = keybindings::get();
+            if keys.is(key, MenuAction::Cancel) {
+                return ItemTargetingResult::Cancel;
+            }
+            if keys.is(key, MenuAction::Confirm) {
+                return if available_cells.contains(&cursor) {
+                    ItemTargetingResult::Targeted(cursor)
+                } else {
+                    ItemTargetingResult::NoResponse
+                };
+            }
+            let delta = match keys.direction(key) {
+                Some((dx, dy)) => Point::new(dx, dy),
+                None => return ItemTargetingResult::NoResponse,
+            };
+
+            // the keyboard cursor can only move within the highlighted cells
+            let moved = cursor + delta;
+            if available_cells.contains(&moved) {
+                ItemTargetingResult::MoveCursor(moved)
+            } else {
+                ItemTargetingResult::NoResponse
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone)]
+pub enum GameOverResult {
+    NoSelection,
+    NewGame,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn cause_of_death(world: &World) -> String {
+    let player_entity = world.fetch::<Entity>();
+    let names = world.read_storage::<Name>();
+    let killer = world
+        .read_storage::<Slain>()
+        .get(*player_entity)
+        .and_then(|slain| slain.killer);
+
+    match killer {
+        Some(killer) if killer == *player_entity => "You were killed by your own hand.".to_string(),
+        Some(killer) => match names.get(killer) {
+            Some(name) => format!("You were killed by a {}.", name.name),
+            None => "You were killed.".to_string(),
+        },
+        None => {
+            let poisoned = world
+                .read_storage::<StatusEffects>()
+                .get(*player_entity)
+                .is_some_and(|statuses| statuses.has(StatusKind::Poison));
+            if poisoned {
+                "You succumbed to poison.".to_string()
+            } else {
+                "You succumbed to your wounds.".to_string()
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn draw_log_entry(ctx: &mut Rltk, x: i32, y: i32, entry: &LogEntry) {
+    ctx.print_color(
+        x,
+        y,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!("{:>5}", entry.turn),
+    );
+    ctx.print_color(x + 6, y, entry.colour, RGB::named(rltk::BLACK), &entry.text);
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone)]
+pub enum LogHistoryResult {
+    Close,
+    Page(usize),
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the whole stored log, a screenful at a time; page 0 holds the newest entries
+pub fn log_history(
+    world: &World,
+    ctx: &mut Rltk,
+    viewport: &Viewport,
+    page: usize,
+) -> LogHistoryResult {
+    let log = world.fetch::<GameLog>();
+    let keys = keybindings::get();
+    let height = viewport.map_height + viewport.log_height;
+    let per_page = (height - 2) as usize;
+    let pages = usize::max(1, log.entries.len().div_ceil(per_page));
+    let page = usize::min(page, pages - 1);
+
+    ctx.draw_box(
+        0,
+        0,
+        viewport.map_width - 1,
+        height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        3,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Message history",
+    );
+    ctx.print_color(
+        3,
+        height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!(
+            "Page {}/{}  {}: older  {}: newer  {}: close",
+            page + 1,
+            pages,
+            keys.menu_key_name(MenuAction::PageUp),
+            keys.menu_key_name(MenuAction::PageDown),
+            keys.menu_key_name(MenuAction::Cancel)
+        ),
+    );
+
+    let end = log.entries.len() - page * per_page;
+    let start = end.saturating_sub(per_page);
+    for (i, entry) in log.entries.range(start..end).enumerate() {
+        draw_log_entry(ctx, 2, 1 + i as i32, entry);
+    }
+
+    match ctx.key {
+        None => LogHistoryResult::Page(page),
+        Some(key) if keys.is(key, MenuAction::PageUp) => {
+            LogHistoryResult::Page(usize::min(page + 1, pages - 1))
+        }
+        Some(key) if keys.is(key, MenuAction::PageDown) => {
+            LogHistoryResult::Page(page.saturating_sub(1))
+        }
+        Some(key) if keys.is(key, MenuAction::Cancel) => LogHistoryResult::Close,
+        Some(key) => match keys.command(key) {
+            PlayerCommand::RunState(RunState::ShowLog { .. }) => LogHistoryResult::Close,
+            _ => LogHistoryResult::Page(page),
+        },
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone)]
+pub enum MapGenResult {
+    Done,
+    Frame { frame: usize, paused: bool },
+}
+
+// how long each snapshot stays on screen while the playback runs
+const MAPGEN_FRAME_MS: f32 = 150.0;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// plays back the builder's snapshots of the current level; the pause key pauses, west and east step while paused
+pub fn map_generation(
+    world: &World,
+    ctx: &mut Rltk,
+    viewport: &Viewport,
+    frame: usize,
+    paused: bool,
+) -> MapGenResult {
+    let mut history = match world.try_fetch_mut::<MapGenHistory>() {
+        Some(history) if !history.snapshots.is_empty() => history,
+        _ => return MapGenResult::Done,
+    };
+    let last = history.snapshots.len() - 1;
+    let frame = usize::min(frame, last);
+    let keys = keybindings::get();
+
+    let mut snapshot = Map::new(world.fetch::<Map>().size.clone());
+    snapshot.tiles = history.snapshots[frame].clone();
+    snapshot.draw_map_overview(viewport, ctx);
+    ctx.print_color(
+        1,
+        viewport.map_height,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{}: step {}/{}{}",
+            history.builder,
+            frame + 1,
+            last + 1,
+            if paused { " (paused)" } else { "" }
+        ),
+    );
+    ctx.print_color(
+        1,
+        viewport.map_height + 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        history.stats.to_string(),
+    );
+    ctx.print_color(
+        1,
+        viewport.map_height + 2,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{}: pause  {}/{}: step  {}: skip",
+            keys.menu_key_name(MenuAction::Pause),
+            keys.key_for(PlayerCommand::Move(-1, 0))
+                .map_or("?", keybindings::key_name),
+            keys.key_for(PlayerCommand::Move(1, 0))
+                .map_or("?", keybindings::key_name),
+            keys.menu_key_name(MenuAction::Cancel)
+        ),
+    );
+
+    // stepping uses the player's own west and east keys
+    if let Some(key) = ctx.key {
+        if keys.is(key, MenuAction::Cancel) {
+            return MapGenResult::Done;
+        }
+        if keys.is(key, MenuAction::Pause) {
+            return MapGenResult::Frame {
+                frame,
+                paused: !paused,
+            };
+        }
+        match keys.direction(key) {
+            Some((1, 0)) => {
+                return MapGenResult::Frame {
+                    frame: usize::min(frame + 1, last),
+                    paused: true,
+                }
+            }
+            Some((-1, 0)) => {
+                return MapGenResult::Frame {
+                    frame: frame.saturating_sub(1),
+                    paused: true,
+                }
+            }
+            _ => {}
+        }
+    }
+    if paused {
+        return MapGenResult::Frame { frame, paused };
+    }
+
+    history.timer += ctx.frame_time_ms;
+    if history.timer < MAPGEN_FRAME_MS {
+        return MapGenResult::Frame { frame, paused };
+    }
+    history.timer = 0.0;
+    if frame == last {
+        MapGenResult::Done
+    } else {
+        MapGenResult::Frame {
+            frame: frame + 1,
+            paused,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn game_over(world: &World, ctx: &mut Rltk, viewport: &Viewport) -> GameOverResult {
+    let player_entity = world.fetch::<Entity>();
+    let (turns, kills) = world
+        .read_storage::<PlayerRecord>()
+        .get(*player_entity)
+        .map_or((0, 0), |record| (record.turns, record.kills));
+    let depth = world.fetch::<Map>().depth;
+
+    let y = viewport.map_height / 2 - 5;
+    let lines = [
+        (
+            RGB::named(rltk::YELLOW),
+            "Your journey has ended!".to_string(),
+        ),
+        (RGB::named(rltk::WHITE), cause_of_death(world)),
+        (
+            RGB::named(rltk::WHITE),
+            format!("You survived {} turns.", turns),
+        ),
+        (
+            RGB::named(rltk::WHITE),
+            format!("You reached depth {}.", depth),
+        ),
+        (
+            RGB::named(rltk::WHITE),
+            format!("You killed {} monsters.", kills),
+        ),
+    ];
+    for (i, (colour, text)) in lines.iter().enumerate() {
+        // leave a gap under the heading
+        let row = if i == 0 { y } else { y + i as i32 + 1 };
+        ctx.print_color_centered(row, *colour, RGB::named(rltk::BLACK), text);
+    }
+    ctx.print_color_centered(
+        y + lines.len() as i32 + 2,
+        RGB::named(rltk::MAGENTA),
+        RGB::named(rltk::BLACK),
+        format!(
+            "Press {} to start a new game.",
+            keybindings::get().menu_key_name(MenuAction::Confirm)
+        ),
+    );
+
+    match ctx.key {
+        Some(key) if keybindings::get().is(key, MenuAction::Confirm) => GameOverResult::NewGame,
+        _ => GameOverResult::NoSelection,
+    }
+}
 
-    ItemTargetingResult::NoResponse
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone)]
+pub enum MainMenuResult {
+    NoSelection {
+        selected: MainMenuSelection,
+        seed: Option<u64>,
+    },
+    Selected {
+        selected: MainMenuSelection,
+        seed: Option<u64>,
+    },
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn key_digit(key: VirtualKeyCode) -> Option<u64> {
+    use VirtualKeyCode::*;
+    let digit = match key {
+        Key0 | Numpad0 => 0,
+        Key1 | Numpad1 => 1,
+        Key2 | Numpad2 => 2,
+        Key3 | Numpad3 => 3,
+        Key4 | Numpad4 => 4,
+        Key5 | Numpad5 => 5,
+        Key6 | Numpad6 => 6,
+        Key7 | Numpad7 => 7,
+        Key8 | Numpad8 => 8,
+        Key9 | Numpad9 => 9,
+        _ => return None,
+    };
+    Some(digit)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// digits typed anywhere on the menu build up the seed, backspace takes them off again
+pub fn main_menu(
+    ctx: &mut Rltk,
+    selected: MainMenuSelection,
+    seed: Option<u64>,
+    load_failed: bool,
+) -> MainMenuResult {
+    let can_continue = saveload_system::save_exists();
+    let (width, _height) = ctx.get_char_size();
+    let entries = [
+        (MainMenuSelection::NewGame, "New Game", true),
+        (MainMenuSelection::Continue, "Continue", can_continue),
+        (MainMenuSelection::Quit, "Quit", true),
+    ];
+
+    ctx.print_color_centered(
+        15,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Rusty Roguelike",
+    );
+
+    // the mouse picks whichever enabled entry it is over
+    let (mouse_x, mouse_y) = ctx.mouse_pos();
+    let mut hovered = None;
+    for (i, (entry, text, enabled)) in entries.iter().enumerate() {
+        let y = 24 + i as i32;
+        let x = (width as i32 - text.len() as i32) / 2;
+        if *enabled && mouse_y == y && mouse_x >= x && mouse_x < x + text.len() as i32 {
+            hovered = Some(*entry);
+        }
+    }
+    let selected = hovered.unwrap_or(selected);
+
+    for (i, (entry, text, enabled)) in entries.iter().enumerate() {
+        let fg = if !enabled {
+            RGB::named(rltk::GRAY)
+        } else if *entry == selected {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color_centered(24 + i as i32, fg, RGB::named(rltk::BLACK), text);
+    }
+
+    let seed_text = match seed {
+        Some(seed) => format!("Seed: {}", seed),
+        None => format!("Seed: {} (type digits to change)", DEFAULT_SEED),
+    };
+    ctx.print_color_centered(
+        29,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        &seed_text,
+    );
+    if load_failed {
+        ctx.print_color_centered(
+            31,
+            RGB::named(rltk::RED),
+            RGB::named(rltk::BLACK),
+            "The save file could not be loaded.",
+        );
+    }
+
+    if hovered.is_some() && ctx.left_click {
+        return MainMenuResult::Selected { selected, seed };
+    }
+
+    // keyboard movement skips over entries that can't be chosen
+    let keys = keybindings::get();
+    let step = |from: MainMenuSelection, delta: i32| -> MainMenuSelection {
+        let count = entries.len() as i32;
+        let mut i = entries.iter().position(|e| e.0 == from).unwrap_or(0) as i32;
+        loop {
+            i = (i + delta).rem_euclid(count);
+            if entries[i as usize].2 {
+                return entries[i as usize].0;
+            }
+        }
+    };
+
+    match ctx.key {
+        None => MainMenuResult::NoSelection { selected, seed },
+        Some(key) if keys.is(key, MenuAction::MenuUp) => MainMenuResult::NoSelection {
+            selected: step(selected, -1),
+            seed,
+        },
+        Some(key) if keys.is(key, MenuAction::MenuDown) => MainMenuResult::NoSelection {
+            selected: step(selected, 1),
+            seed,
+        },
+        Some(key)
+            if keys.is(key, MenuAction::Confirm)
+                && entries.iter().any(|e| e.0 == selected && e.2) =>
+        {
+            MainMenuResult::Selected { selected, seed }
+        }
+        // the seed is typed in, so its digits and backspace aren't bindable
+        Some(key) => match key {
+            VirtualKeyCode::Back => MainMenuResult::NoSelection {
+                selected,
+                seed: seed.map(|seed| seed / 10).filter(|seed| *seed > 0),
+            },
+            _ => match key_digit(key) {
+                Some(digit) => MainMenuResult::NoSelection {
+                    selected,
+                    seed: seed
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|seed| seed.checked_add(digit))
+                        .or(seed),
+                },
+                None => MainMenuResult::NoSelection { selected, seed },
+            },
+        },
+    }
 }
@@ -0,0 +1,191 @@
+use super::map_builders::MapGenHistory;
+use super::player::{perform_command, PlayerCommand};
+use super::replay;
+use super::{
+    arg_value, new_world, seed_arg, CombatStats, GameLog, Map, PlayerRecord, RunState, State,
+    Viewport, DEFAULT_SEED,
+};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+const DEFAULT_TURN_LIMIT: i32 = 1000;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// drives the game without a terminal: commands go in, turns come out
+pub struct HeadlessDriver {
+    pub state: State,
+    pub turn: i32,
+}
+
+impl HeadlessDriver {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn new(seed: u64) -> HeadlessDriver {
+        let mut driver = HeadlessDriver {
+            state: State {
+                ecs: new_world(seed),
+                viewport: Viewport::default(),
+                show_mapgen: false,
+            },
+            turn: 0,
+        };
+        driver.run_until_input(RunState::PreRun);
+        driver
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn player_dead(&self) -> bool {
+        self.state.player_dead()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn step(&mut self, command: PlayerCommand) {
+        let runstate = perform_command(&mut self.state, command);
+        self.run_until_input(runstate);
+        replay::finish_step(&mut self.state.ecs);
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn run_until_input(&mut self, mut runstate: RunState) {
+        while runstate != RunState::AwaitingInput && runstate != RunState::GameOver {
+            // systems read the current state from the world, same as under the window
+            *self.state.ecs.write_resource::<RunState>() = runstate;
+            runstate = match self.state.advance(runstate) {
+                // menus and targeting need a window, so they end the step
+                RunState::ShowInventory
+                | RunState::ShowDropItem
+                | RunState::ShowTargeting { .. }
+                | RunState::ShowLog { .. } => RunState::AwaitingInput,
+                next => next,
+            };
+        }
+        *self.state.ecs.write_resource::<RunState>() = runstate;
+
+        // rests and explores play out several turns inside one step, so the player's own count is the one to trust
+        let player_entity = *self.state.ecs.fetch::<Entity>();
+        self.turn = self
+            .state
+            .ecs
+            .read_storage::<PlayerRecord>()
+            .get(player_entity)
+            .map_or(self.turn, |record| record.turns);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// one command per line: left, right, up, down, up-left, up-right, down-left, down-right, get, descend, wait, rest, explore, close, use <slot> [x y], drop <slot>,
+// save, load
+pub fn parse_command(line: &str) -> Option<PlayerCommand> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        ["left"] => PlayerCommand::Move(-1, 0),
+        ["right"] => PlayerCommand::Move(1, 0),
+        ["up"] => PlayerCommand::Move(0, -1),
+        ["down"] => PlayerCommand::Move(0, 1),
+        ["up-left"] => PlayerCommand::Move(-1, -1),
+        ["up-right"] => PlayerCommand::Move(1, -1),
+        ["down-left"] => PlayerCommand::Move(-1, 1),
+        ["down-right"] => PlayerCommand::Move(1, 1),
+        ["get"] => PlayerCommand::GetItem,
+        ["descend"] => PlayerCommand::Descend,
+        ["wait"] => PlayerCommand::Wait,
+        ["rest"] => PlayerCommand::Rest,
+        ["explore"] => PlayerCommand::Explore,
+        ["close"] => PlayerCommand::CloseDoor,
+        ["use", slot] => PlayerCommand::UseItem {
+            slot: slot.parse().ok()?,
+            target: None,
+        },
+        ["use", slot, x, y] => PlayerCommand::UseItem {
+            slot: slot.parse().ok()?,
+            target: Some(Point::new(x.parse::<i32>().ok()?, y.parse::<i32>().ok()?)),
+        },
+        ["drop", slot] => PlayerCommand::DropItem {
+            slot: slot.parse().ok()?,
+        },
+        ["save"] => PlayerCommand::RunState(RunState::SaveGame),
+        ["load"] => PlayerCommand::RunState(RunState::LoadGame),
+        _ => return None,
+    };
+    Some(command)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// --headless [--seed <n> | --random-seed] [--turns <n>] [--commands <file>] [--record <file>]
+// --headless --replay <file>
+// without a command file the player wanders at random, which is enough for balance runs
+pub fn main(args: &[String]) -> rltk::BError {
+    if let Some(path) = arg_value(args, "--replay") {
+        return replay::run_headless(path);
+    }
+    let seed = seed_arg(args)?.unwrap_or(DEFAULT_SEED);
+    let turn_limit = match arg_value(args, "--turns") {
+        Some(value) => value.parse()?,
+        None => DEFAULT_TURN_LIMIT,
+    };
+    let mut commands = match arg_value(args, "--commands") {
+        Some(path) => {
+            let mut commands = Vec::new();
+            for (line_number, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let command = parse_command(line).ok_or_else(|| {
+                    format!("{}:{}: unknown command '{}'", path, line_number + 1, line)
+                })?;
+                commands.push(command);
+            }
+            Some(commands.into_iter())
+        }
+        None => None,
+    };
+
+    let mut driver = HeadlessDriver::new(seed);
+    if let Some(path) = arg_value(args, "--record") {
+        replay::start_recording(&mut driver.state.ecs, path);
+    }
+    let mut bot_rng = RandomNumberGenerator::seeded(seed);
+    while !driver.player_dead() && driver.turn < turn_limit {
+        let command = match commands.as_mut() {
+            Some(commands) => match commands.next() {
+                Some(command) => command,
+                None => break,
+            },
+            None => match bot_rng.roll_dice(1, 4) {
+                1 => PlayerCommand::Move(-1, 0),
+                2 => PlayerCommand::Move(1, 0),
+                3 => PlayerCommand::Move(0, -1),
+                _ => PlayerCommand::Move(0, 1),
+            },
+        };
+        driver.step(command);
+    }
+
+    let ecs = &driver.state.ecs;
+    let player_entity = ecs.fetch::<Entity>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let hp = combat_stats.get(*player_entity).map_or(0, |stats| stats.hp);
+    println!(
+        "seed {}: {} after {} turns, player hp {}",
+        seed,
+        if driver.player_dead() {
+            "died"
+        } else {
+            "survived"
+        },
+        driver.turn,
+        hp
+    );
+    // the level the run ended on, even if the log has since dropped its entry
+    if let Some(history) = ecs.try_fetch::<MapGenHistory>() {
+        println!(
+            "  level {} ({}): {}",
+            ecs.fetch::<Map>().depth,
+            history.builder,
+            history.stats
+        );
+    }
+    for entry in ecs.fetch::<GameLog>().entries.iter() {
+        println!("  {:>5}  {}", entry.turn, entry.text);
+    }
+    Ok(())
+}
@@ -1,9 +1,49 @@
 use super::{
-    gamelog::GameLog, CombatStats, Consumable, InBackpack, Name, Position, ProvidesHealing,
-    WantsToConsumeItem, WantsToPickupItem,
+    gamelog::{GameLog, LogCategory},
+    AreaOfEffect, CombatStats, Confusion, Consumable, Equippable, Equipped, InBackpack,
+    InflictsDamage, Map, Name, Poison, Position, ProvidesHealing, Regeneration, Sleep,
+    StatusEffect, StatusEffects, StatusKind, SufferDamage, WantsToDropItem, WantsToPickupItem,
+    WantsToRemoveItem, WantsToUseItem,
 };
+use rltk::{field_of_view, Point};
 use specs::prelude::*;
 
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn blast_tiles(map: &Map, target: Point, radius: i32) -> Vec<Point> {
+    // the blast only reaches tiles with a clear line from the target
+    let mut tiles = field_of_view(target, radius, map);
+    tiles.retain(|p| map.size.contains(&Position::new(p)));
+    tiles
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// everything the owner carries, backpack first and then what they have equipped;
+// the inventory menu and numbered item commands share this order
+pub fn inventory_items(world: &World, owner: Entity) -> Vec<(Entity, bool)> {
+    let entities = world.entities();
+    let backpack = world.read_storage::<InBackpack>();
+    let equipped = world.read_storage::<Equipped>();
+
+    let carried = (&entities, &backpack)
+        .join()
+        .filter(|item| item.1.owner == owner)
+        .map(|item| (item.0, false));
+    let worn = (&entities, &equipped)
+        .join()
+        .filter(|item| item.1.owner == owner)
+        .map(|item| (item.0, true));
+    carried.chain(worn).collect()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// what other creatures do is only worth logging when the player can see them
+fn in_view(map: &Map, position: Option<&Position>) -> bool {
+    match position {
+        Some(pos) => map.visible_tiles[map.position_idx(*pos)],
+        None => false,
+    }
+}
+
 // ------------------------------------------------------------------------------------------------------------------ //
 pub struct ItemCollectionSystem {}
 
@@ -13,6 +53,7 @@ impl<'a> System<'a> for ItemCollectionSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
         WriteStorage<'a, WantsToPickupItem>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
@@ -20,10 +61,11 @@ impl<'a> System<'a> for ItemCollectionSystem {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) =
+        let (player_entity, mut gamelog, map, mut wants_pickup, mut positions, names, mut backpack) =
             data;
 
         for pickup in wants_pickup.join() {
+            let seen = in_view(&map, positions.get(pickup.collected_by));
             positions.remove(pickup.item);
             backpack
                 .insert(
@@ -35,10 +77,19 @@ impl<'a> System<'a> for ItemCollectionSystem {
                 .expect("Unable to insert backpack entry");
 
             if pickup.collected_by == *player_entity {
-                gamelog.entries.push(format!(
-                    "You pick up the {}.",
-                    names.get(pickup.item).unwrap().name
-                ));
+                gamelog.add(
+                    LogCategory::Item,
+                    format!("You pick up the {}.", names.get(pickup.item).unwrap().name),
+                );
+            } else if seen {
+                gamelog.add(
+                    LogCategory::Item,
+                    format!(
+                        "{} picks up the {}.",
+                        names.get(pickup.collected_by).unwrap().name,
+                        names.get(pickup.item).unwrap().name
+                    ),
+                );
             }
         }
 
@@ -55,49 +106,315 @@ impl<'a> System<'a> for ItemUseSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
-        WriteStorage<'a, WantsToConsumeItem>,
+        WriteStorage<'a, WantsToUseItem>,
         ReadStorage<'a, Name>,
-        WriteStorage<'a, Consumable>,
+        ReadStorage<'a, Consumable>,
         ReadStorage<'a, ProvidesHealing>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, AreaOfEffect>,
         WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Equippable>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Confusion>,
+        ReadStorage<'a, Sleep>,
+        ReadStorage<'a, Poison>,
+        ReadStorage<'a, Regeneration>,
+        WriteStorage<'a, StatusEffects>,
     );
     fn run(&mut self, data: Self::SystemData) {
         let (
             player_entity,
             mut gamelog,
+            map,
             entities,
-            mut wants_consume,
+            mut wants_use,
             names,
             consumables,
             healings,
+            inflict_damage,
+            aoe,
             mut combat_stats,
+            mut suffer_damage,
+            equippable,
+            mut equipped,
+            mut backpack,
+            positions,
+            confusion,
+            sleep,
+            poison,
+            regeneration,
+            mut status_effects,
         ) = data;
 
-        for (entity, consume, stats) in (&entities, &wants_consume, &mut combat_stats).join() {
-            let heal_item = healings.get(consume.item);
-            match heal_item {
-                None => {}
-                Some(heal) => {
+        for (entity, use_item) in (&entities, &wants_use).join() {
+            let mut used_item = false;
+            let item_name = &names.get(use_item.item).unwrap().name;
+            let user_name = &names.get(entity).unwrap().name;
+            let seen = in_view(&map, positions.get(entity));
+
+            // equipment goes into its slot, and whatever was there goes back in the backpack
+            if let Some(can_equip) = equippable.get(use_item.item) {
+                let slot = can_equip.slot;
+                let previous: Vec<Entity> = (&entities, &equipped)
+                    .join()
+                    .filter(|(_item, worn)| worn.owner == entity && worn.slot == slot)
+                    .map(|(item, _worn)| item)
+                    .collect();
+                for item in previous {
+                    equipped.remove(item);
+                    backpack
+                        .insert(item, InBackpack { owner: entity })
+                        .expect("Unable to insert backpack entry");
+                    if entity == *player_entity {
+                        gamelog.add(
+                            LogCategory::Item,
+                            format!("You unequip {}.", names.get(item).unwrap().name),
+                        );
+                    }
+                }
+
+                backpack.remove(use_item.item);
+                equipped
+                    .insert(
+                        use_item.item,
+                        Equipped {
+                            owner: entity,
+                            slot,
+                        },
+                    )
+                    .expect("Unable to equip item");
+                if entity == *player_entity {
+                    gamelog.add(LogCategory::Item, format!("You equip {}.", item_name));
+                }
+            }
+
+            // healing items apply to the user
+            if let Some(heal) = healings.get(use_item.item) {
+                if let Some(stats) = combat_stats.get_mut(entity) {
                     stats.hp = i32::min(stats.max_hp, stats.hp + heal.amount);
                     if entity == *player_entity {
-                        gamelog.entries.push(format!(
-                            "You drink the {}, healing {} hp.",
-                            names.get(consume.item).unwrap().name,
-                            heal.amount
-                        ));
+                        gamelog.add(
+                            LogCategory::Item,
+                            format!("You drink the {}, healing {} hp.", item_name, heal.amount),
+                        );
+                    } else if seen {
+                        gamelog.add(
+                            LogCategory::Item,
+                            format!(
+                                "{} drinks the {}, healing {} hp.",
+                                user_name, item_name, heal.amount
+                            ),
+                        );
                     }
+                    used_item = true;
+                }
+            }
 
-                    let consumable = consumables.get(consume.item);
-                    match consumable {
-                        None => {}
-                        Some(_) => {
-                            entities.delete(consume.item).expect("Delete failed");
-                        }
+            // targeted items apply to whatever stands on the target tile, or anywhere in the blast;
+            // anything else applies to the user
+            let targets: Vec<Entity> = match use_item.target {
+                None => vec![entity],
+                Some(target) => {
+                    let tiles = match aoe.get(use_item.item) {
+                        None => vec![target],
+                        Some(area) => blast_tiles(&map, target, area.radius),
+                    };
+                    tiles
+                        .iter()
+                        .flat_map(|tile| map.tile_content[map.xy_idx(tile.x, tile.y)].iter())
+                        .filter(|mob| combat_stats.get(**mob).is_some())
+                        .copied()
+                        .collect()
+                }
+            };
+
+            if let (Some(damage), Some(_target)) =
+                (inflict_damage.get(use_item.item), use_item.target)
+            {
+                for mob in targets.iter() {
+                    SufferDamage::new_damage(&mut suffer_damage, *mob, damage.amount, Some(entity));
+                    let mob_name = &names.get(*mob).unwrap().name;
+                    if entity == *player_entity {
+                        gamelog.add(
+                            LogCategory::Combat,
+                            format!(
+                                "You use {} on {}, inflicting {} hp.",
+                                item_name, mob_name, damage.amount
+                            ),
+                        );
+                    } else if seen {
+                        gamelog.add(
+                            LogCategory::Combat,
+                            format!(
+                                "{} uses {} on {}, inflicting {} hp.",
+                                user_name, item_name, mob_name, damage.amount
+                            ),
+                        );
+                    }
+                }
+                used_item |= !targets.is_empty();
+            }
+
+            let effects = [
+                confusion.get(use_item.item).map(|c| StatusEffect {
+                    kind: StatusKind::Confusion,
+                    turns: c.turns,
+                    potency: 0,
+                    source: Some(entity),
+                }),
+                sleep.get(use_item.item).map(|s| StatusEffect {
+                    kind: StatusKind::Sleep,
+                    turns: s.turns,
+                    potency: 0,
+                    source: Some(entity),
+                }),
+                poison.get(use_item.item).map(|p| StatusEffect {
+                    kind: StatusKind::Poison,
+                    turns: p.turns,
+                    potency: p.damage,
+                    source: Some(entity),
+                }),
+                regeneration.get(use_item.item).map(|r| StatusEffect {
+                    kind: StatusKind::Regeneration,
+                    turns: r.turns,
+                    potency: r.amount,
+                    source: Some(entity),
+                }),
+            ];
+            for effect in effects.iter().flatten() {
+                for mob in targets.iter() {
+                    StatusEffects::apply(&mut status_effects, *mob, *effect);
+                    if *mob == *player_entity {
+                        gamelog.add(
+                            LogCategory::Combat,
+                            format!("You are {}.", effect.kind.label()),
+                        );
+                    } else if in_view(&map, positions.get(*mob)) {
+                        gamelog.add(
+                            LogCategory::Combat,
+                            format!(
+                                "{} is {}.",
+                                names.get(*mob).unwrap().name,
+                                effect.kind.label()
+                            ),
+                        );
                     }
                 }
+                used_item |= !targets.is_empty();
+            }
+
+            // aimed at an empty tile, the item isn't used up
+            if use_item.target.is_some() && targets.is_empty() && entity == *player_entity {
+                gamelog.add(LogCategory::Item, "There is nothing there.");
+            }
+
+            if used_item && consumables.get(use_item.item).is_some() {
+                entities.delete(use_item.item).expect("Delete failed");
+            }
+        }
+        wants_use.clear();
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub struct ItemRemoveSystem {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+impl<'a> System<'a> for ItemRemoveSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        WriteStorage<'a, WantsToRemoveItem>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, InBackpack>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            mut wants_remove,
+            names,
+            mut equipped,
+            mut backpack,
+        ) = data;
+
+        for (entity, to_remove) in (&entities, &wants_remove).join() {
+            equipped.remove(to_remove.item);
+            backpack
+                .insert(to_remove.item, InBackpack { owner: entity })
+                .expect("Unable to insert backpack entry");
+
+            if entity == *player_entity {
+                gamelog.add(
+                    LogCategory::Item,
+                    format!("You unequip {}.", names.get(to_remove.item).unwrap().name),
+                );
             }
         }
-        wants_consume.clear();
+
+        wants_remove.clear();
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub struct ItemDropSystem {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+impl<'a> System<'a> for ItemDropSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        WriteStorage<'a, WantsToDropItem>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Equipped>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            mut wants_drop,
+            names,
+            mut positions,
+            mut backpack,
+            mut equipped,
+        ) = data;
+
+        for (entity, to_drop) in (&entities, &wants_drop).join() {
+            // the item lands on the owner's tile
+            let dropper_pos = match positions.get(entity) {
+                Some(pos) => *pos,
+                None => continue,
+            };
+            positions
+                .insert(to_drop.item, dropper_pos)
+                .expect("Unable to insert position");
+            backpack.remove(to_drop.item);
+            equipped.remove(to_drop.item);
+
+            if entity == *player_entity {
+                gamelog.add(
+                    LogCategory::Item,
+                    format!("You drop the {}.", names.get(to_drop.item).unwrap().name),
+                );
+            }
+        }
+
+        wants_drop.clear();
     }
 }
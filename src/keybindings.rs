@@ -0,0 +1,372 @@
+use super::player::PlayerCommand;
+use super::raws::RawsError;
+use super::RunState;
+use rltk::VirtualKeyCode;
+use serde::{Deserialize, Deserializer};
+use std::sync::OnceLock;
+
+pub const KEYBINDINGS_PATH: &str = "raws/keys.json";
+
+static KEYBINDINGS: OnceLock<KeyBindings> = OnceLock::new();
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the keys a binding file can name, spelled the way the key codes are
+const KEY_NAMES: &[(&str, VirtualKeyCode)] = &[
+    ("A", VirtualKeyCode::A),
+    ("B", VirtualKeyCode::B),
+    ("C", VirtualKeyCode::C),
+    ("D", VirtualKeyCode::D),
+    ("E", VirtualKeyCode::E),
+    ("F", VirtualKeyCode::F),
+    ("G", VirtualKeyCode::G),
+    ("H", VirtualKeyCode::H),
+    ("I", VirtualKeyCode::I),
+    ("J", VirtualKeyCode::J),
+    ("K", VirtualKeyCode::K),
+    ("L", VirtualKeyCode::L),
+    ("M", VirtualKeyCode::M),
+    ("N", VirtualKeyCode::N),
+    ("O", VirtualKeyCode::O),
+    ("P", VirtualKeyCode::P),
+    ("Q", VirtualKeyCode::Q),
+    ("R", VirtualKeyCode::R),
+    ("S", VirtualKeyCode::S),
+    ("T", VirtualKeyCode::T),
+    ("U", VirtualKeyCode::U),
+    ("V", VirtualKeyCode::V),
+    ("W", VirtualKeyCode::W),
+    ("X", VirtualKeyCode::X),
+    ("Y", VirtualKeyCode::Y),
+    ("Z", VirtualKeyCode::Z),
+    ("Key0", VirtualKeyCode::Key0),
+    ("Key1", VirtualKeyCode::Key1),
+    ("Key2", VirtualKeyCode::Key2),
+    ("Key3", VirtualKeyCode::Key3),
+    ("Key4", VirtualKeyCode::Key4),
+    ("Key5", VirtualKeyCode::Key5),
+    ("Key6", VirtualKeyCode::Key6),
+    ("Key7", VirtualKeyCode::Key7),
+    ("Key8", VirtualKeyCode::Key8),
+    ("Key9", VirtualKeyCode::Key9),
+    ("Numpad0", VirtualKeyCode::Numpad0),
+    ("Numpad1", VirtualKeyCode::Numpad1),
+    ("Numpad2", VirtualKeyCode::Numpad2),
+    ("Numpad3", VirtualKeyCode::Numpad3),
+    ("Numpad4", VirtualKeyCode::Numpad4),
+    ("Numpad5", VirtualKeyCode::Numpad5),
+    ("Numpad6", VirtualKeyCode::Numpad6),
+    ("Numpad7", VirtualKeyCode::Numpad7),
+    ("Numpad8", VirtualKeyCode::Numpad8),
+    ("Numpad9", VirtualKeyCode::Numpad9),
+    ("NumpadDecimal", VirtualKeyCode::NumpadDecimal),
+    ("NumpadEnter", VirtualKeyCode::NumpadEnter),
+    ("Left", VirtualKeyCode::Left),
+    ("Right", VirtualKeyCode::Right),
+    ("Up", VirtualKeyCode::Up),
+    ("Down", VirtualKeyCode::Down),
+    ("Home", VirtualKeyCode::Home),
+    ("End", VirtualKeyCode::End),
+    ("PageUp", VirtualKeyCode::PageUp),
+    ("PageDown", VirtualKeyCode::PageDown),
+    ("Insert", VirtualKeyCode::Insert),
+    ("Delete", VirtualKeyCode::Delete),
+    ("Space", VirtualKeyCode::Space),
+    ("Return", VirtualKeyCode::Return),
+    ("Tab", VirtualKeyCode::Tab),
+    ("Escape", VirtualKeyCode::Escape),
+    ("Back", VirtualKeyCode::Back),
+    ("Comma", VirtualKeyCode::Comma),
+    ("Period", VirtualKeyCode::Period),
+    ("Slash", VirtualKeyCode::Slash),
+    ("Semicolon", VirtualKeyCode::Semicolon),
+    ("Minus", VirtualKeyCode::Minus),
+    ("Equals", VirtualKeyCode::Equals),
+    ("F1", VirtualKeyCode::F1),
+    ("F2", VirtualKeyCode::F2),
+    ("F3", VirtualKeyCode::F3),
+    ("F4", VirtualKeyCode::F4),
+    ("F5", VirtualKeyCode::F5),
+    ("F6", VirtualKeyCode::F6),
+    ("F7", VirtualKeyCode::F7),
+    ("F8", VirtualKeyCode::F8),
+    ("F9", VirtualKeyCode::F9),
+    ("F10", VirtualKeyCode::F10),
+    ("F11", VirtualKeyCode::F11),
+    ("F12", VirtualKeyCode::F12),
+];
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn key_name(key: VirtualKeyCode) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|(_name, code)| *code == key)
+        .map_or("?", |(name, _code)| name)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn keys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<VirtualKeyCode>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|text| {
+            KEY_NAMES
+                .iter()
+                .find(|(name, _code)| name == text)
+                .map(|(_name, code)| *code)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown key \"{}\"", text)))
+        })
+        .collect()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// every command has to be listed, so a binding file can't quietly leave one out
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyBindingsRaw {
+    #[serde(deserialize_with = "keys")]
+    move_west: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_east: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_north: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_south: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_north_west: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_north_east: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_south_west: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    move_south_east: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    wait: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    rest: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    explore: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    close_door: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    get: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    descend: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    inventory: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    drop: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    log: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    save: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    load: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    confirm: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    cancel: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    page_up: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    page_down: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    menu_up: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    menu_down: Vec<VirtualKeyCode>,
+    #[serde(deserialize_with = "keys")]
+    pause: Vec<VirtualKeyCode>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// what a key does on the menus and other screens that don't take commands
+#[derive(PartialEq, Copy, Clone)]
+pub enum MenuAction {
+    Confirm,
+    Cancel,
+    PageUp,
+    PageDown,
+    MenuUp,
+    MenuDown,
+    Pause,
+}
+
+impl KeyBindingsRaw {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn menu_actions(&mut self) -> Vec<(Vec<VirtualKeyCode>, MenuAction)> {
+        vec![
+            (std::mem::take(&mut self.confirm), MenuAction::Confirm),
+            (std::mem::take(&mut self.cancel), MenuAction::Cancel),
+            (std::mem::take(&mut self.page_up), MenuAction::PageUp),
+            (std::mem::take(&mut self.page_down), MenuAction::PageDown),
+            (std::mem::take(&mut self.menu_up), MenuAction::MenuUp),
+            (std::mem::take(&mut self.menu_down), MenuAction::MenuDown),
+            (std::mem::take(&mut self.pause), MenuAction::Pause),
+        ]
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn commands(self) -> Vec<(&'static str, Vec<VirtualKeyCode>, PlayerCommand)> {
+        vec![
+            ("move_west", self.move_west, PlayerCommand::Move(-1, 0)),
+            ("move_east", self.move_east, PlayerCommand::Move(1, 0)),
+            ("move_north", self.move_north, PlayerCommand::Move(0, -1)),
+            ("move_south", self.move_south, PlayerCommand::Move(0, 1)),
+            (
+                "move_north_west",
+                self.move_north_west,
+                PlayerCommand::Move(-1, -1),
+            ),
+            (
+                "move_north_east",
+                self.move_north_east,
+                PlayerCommand::Move(1, -1),
+            ),
+            (
+                "move_south_west",
+                self.move_south_west,
+                PlayerCommand::Move(-1, 1),
+            ),
+            (
+                "move_south_east",
+                self.move_south_east,
+                PlayerCommand::Move(1, 1),
+            ),
+            ("wait", self.wait, PlayerCommand::Wait),
+            ("rest", self.rest, PlayerCommand::Rest),
+            ("explore", self.explore, PlayerCommand::Explore),
+            ("close_door", self.close_door, PlayerCommand::CloseDoor),
+            ("get", self.get, PlayerCommand::GetItem),
+            ("descend", self.descend, PlayerCommand::Descend),
+            (
+                "inventory",
+                self.inventory,
+                PlayerCommand::RunState(RunState::ShowInventory),
+            ),
+            (
+                "drop",
+                self.drop,
+                PlayerCommand::RunState(RunState::ShowDropItem),
+            ),
+            (
+                "log",
+                self.log,
+                PlayerCommand::RunState(RunState::ShowLog { page: 0 }),
+            ),
+            (
+                "save",
+                self.save,
+                PlayerCommand::RunState(RunState::SaveGame),
+            ),
+            (
+                "load",
+                self.load,
+                PlayerCommand::RunState(RunState::LoadGame),
+            ),
+        ]
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// a list rather than a map: it's short, and the first key for a command is the one the screen shows.
+// menu keys are only read where no command is, so they can share keys with commands and with each other
+pub struct KeyBindings {
+    bindings: Vec<(VirtualKeyCode, PlayerCommand)>,
+    menu: Vec<(VirtualKeyCode, MenuAction)>,
+}
+
+impl KeyBindings {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn command(&self, key: VirtualKeyCode) -> PlayerCommand {
+        self.bindings
+            .iter()
+            .find(|(bound, _command)| *bound == key)
+            .map_or(PlayerCommand::None, |(_key, command)| *command)
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // targeting and other cursors move with the same keys as the player
+    pub fn direction(&self, key: VirtualKeyCode) -> Option<(i32, i32)> {
+        match self.command(key) {
+            PlayerCommand::Move(dx, dy) => Some((dx, dy)),
+            _ => None,
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn key_for(&self, command: PlayerCommand) -> Option<VirtualKeyCode> {
+        self.bindings
+            .iter()
+            .find(|(_key, bound)| *bound == command)
+            .map(|(key, _command)| *key)
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn is(&self, key: VirtualKeyCode, action: MenuAction) -> bool {
+        self.menu
+            .iter()
+            .any(|(bound, bound_action)| *bound == key && *bound_action == action)
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // the name of the first key for a menu action, for the help lines
+    pub fn menu_key_name(&self, action: MenuAction) -> &'static str {
+        self.menu
+            .iter()
+            .find(|(_key, bound)| *bound == action)
+            .map_or("?", |(key, _action)| key_name(*key))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn read(file: &str) -> Result<KeyBindings, RawsError> {
+    let text = std::fs::read_to_string(file).map_err(|error| RawsError::Io {
+        file: file.to_string(),
+        error,
+    })?;
+
+    let de = &mut serde_json::Deserializer::from_str(&text);
+    let mut raw: KeyBindingsRaw =
+        serde_path_to_error::deserialize(de).map_err(|e| RawsError::Schema {
+            file: file.to_string(),
+            field: e.path().to_string(),
+            message: e.inner().to_string(),
+        })?;
+
+    let mut menu: Vec<(VirtualKeyCode, MenuAction)> = Vec::new();
+    for (keys, action) in raw.menu_actions() {
+        menu.extend(keys.into_iter().map(|key| (key, action)));
+    }
+
+    let mut bindings: Vec<(VirtualKeyCode, PlayerCommand)> = Vec::new();
+    let mut owners: Vec<&str> = Vec::new();
+    for (field, keys, command) in raw.commands() {
+        for (i, key) in keys.into_iter().enumerate() {
+            if let Some(taken) = bindings.iter().position(|(bound, _command)| *bound == key) {
+                return Err(RawsError::Schema {
+                    file: file.to_string(),
+                    field: format!("{}[{}]", field, i),
+                    message: format!(
+                        "\"{}\" is already bound to {}",
+                        key_name(key),
+                        owners[taken]
+                    ),
+                });
+            }
+            bindings.push((key, command));
+            owners.push(field);
+        }
+    }
+    Ok(KeyBindings { bindings, menu })
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// load at startup, like the raws, so a broken file is reported before the window opens
+pub fn load(file: &str) -> Result<(), RawsError> {
+    let bindings = read(file)?;
+    let _ = KEYBINDINGS.set(bindings);
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn get() -> &'static KeyBindings {
+    KEYBINDINGS.get_or_init(|| read(KEYBINDINGS_PATH).unwrap_or_else(|e| panic!("{}", e)))
+}
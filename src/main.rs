@@ -1,5 +1,6 @@
-use rltk::{GameState, Point, Rltk};
+use rltk::{GameState, Point, RandomNumberGenerator, Rltk};
 use specs::prelude::*;
+use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
 mod components;
 use components::*;
 mod map;
@@ -20,33 +21,59 @@ mod damage_system;
 use damage_system::DamageSystem;
 mod map_indexing_system;
 use map_indexing_system::MapIndexingSystem;
+mod door_system;
+use door_system::DoorSystem;
 mod gamelog;
-pub use gamelog::GameLog;
+pub use gamelog::{GameLog, LogCategory};
 mod gui;
+mod headless;
 //use gui::ItemMenuResult;
 mod inventory_system;
+mod keybindings;
+mod map_builders;
+use map_builders::MapGenHistory;
+mod raws;
+mod replay;
+mod saveload_system;
+mod spawn_table;
 mod spawner;
-use inventory_system::{ItemCollectionSystem, ItemUseSystem};
+mod status_system;
+use inventory_system::{ItemCollectionSystem, ItemDropSystem, ItemRemoveSystem, ItemUseSystem};
+use status_system::StatusEffectSystem;
+
+// used when no seed is given
+pub const DEFAULT_SEED: u64 = 2;
 
 // ------------------------------------------------------------------------------------------------------------------ //
 pub struct State {
     pub viewport: Viewport,
     pub ecs: World,
+    // play each new level's generation back before it starts
+    pub show_mapgen: bool,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
 impl State {
     fn run_systems(&mut self) {
-        let mut use_items = ItemUseSystem {};
-        use_items.run_now(&self.ecs);
         let mut vis = VisibilitySystem {};
         vis.run_now(&self.ecs);
         let mut mob = MonsterAI {};
         mob.run_now(&self.ecs);
+        let mut doors = DoorSystem {};
+        doors.run_now(&self.ecs);
         let mut mapindex = MapIndexingSystem {};
         mapindex.run_now(&self.ecs);
+        // after the monsters, so their item intents resolve on the turn they were made
+        let mut use_items = ItemUseSystem {};
+        use_items.run_now(&self.ecs);
+        let mut remove_items = ItemRemoveSystem {};
+        remove_items.run_now(&self.ecs);
+        let mut drop_items = ItemDropSystem {};
+        drop_items.run_now(&self.ecs);
         let mut combat = MeleeCombatSystem {};
         combat.run_now(&self.ecs);
+        let mut status = StatusEffectSystem {};
+        status.run_now(&self.ecs);
         let mut damage = DamageSystem {};
         damage.run_now(&self.ecs);
         damage_system::delete_the_dead(&mut self.ecs);
@@ -54,105 +81,385 @@ impl State {
         pickup.run_now(&self.ecs);
         self.ecs.maintain();
     }
-}
 
-// ------------------------------------------------------------------------------------------------------------------ //
-impl GameState for State {
-    fn tick(&mut self, ctx: &mut Rltk) {
-        ctx.cls();
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn player_dead(&self) -> bool {
+        let player_entity = self.ecs.fetch::<Entity>();
+        let combat_stats = self.ecs.read_storage::<CombatStats>();
+        match combat_stats.get(*player_entity) {
+            None => true,
+            Some(stats) => stats.hp <= 0,
+        }
+    }
 
-        let mut newrunstate;
-        {
-            let runstate = self.ecs.fetch::<RunState>();
-            newrunstate = *runstate;
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // once the player is dead nothing else happens until a new game is started
+    fn unless_dead(&self, next: RunState) -> RunState {
+        if self.player_dead() {
+            RunState::GameOver
+        } else {
+            next
         }
+    }
 
-        let (rs, should_show_inventory) = match newrunstate {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // throws the whole world away and starts again on a fresh map, seeded from the old one
+    fn new_game(&mut self) {
+        let seed = self
+            .ecs
+            .write_resource::<RandomNumberGenerator>()
+            .next_u64();
+        self.ecs = new_world(seed);
+        replay::start_recording(&mut self.ecs, replay::REPLAY_PATH);
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // menu picks go through the same command as a typed one, so a replay sees them too
+    fn use_item(&mut self, item: Entity, target: Option<Point>) -> RunState {
+        match item_slot(&self.ecs, item) {
+            Some(slot) => perform_command(self, PlayerCommand::UseItem { slot, target }),
+            None => RunState::AwaitingInput,
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // steps every run state that doesn't need a menu, the targeting cursor or player input
+    pub fn advance(&mut self, runstate: RunState) -> RunState {
+        match runstate {
             RunState::PreRun => {
                 self.run_systems();
-                (RunState::AwaitingInput, false)
+                self.unless_dead(RunState::AwaitingInput)
             }
-            RunState::AwaitingInput => (player_input(self, ctx), false),
             RunState::PlayerTurn => {
                 self.run_systems();
-                (RunState::MonsterTurn, false)
+                let player_entity = *self.ecs.fetch::<Entity>();
+                if let Some(record) = self
+                    .ecs
+                    .write_storage::<PlayerRecord>()
+                    .get_mut(player_entity)
+                {
+                    record.turns += 1;
+                }
+                self.unless_dead(RunState::MonsterTurn)
             }
             RunState::MonsterTurn => {
                 self.run_systems();
-                (RunState::AwaitingInput, false)
+                self.unless_dead(RunState::AwaitingInput)
+            }
+            RunState::Activity(activity) => {
+                // each step plays out a whole turn before the next one is picked
+                let items_before = items_in_view(&self.ecs);
+                if !activity_step(self, activity) {
+                    return RunState::AwaitingInput;
+                }
+                let mut next = RunState::PlayerTurn;
+                while next == RunState::PlayerTurn || next == RunState::MonsterTurn {
+                    *self.ecs.write_resource::<RunState>() = next;
+                    next = self.advance(next);
+                }
+                if next == RunState::AwaitingInput
+                    && !activity_interrupted(&mut self.ecs, activity, &items_before)
+                {
+                    RunState::Activity(activity)
+                } else {
+                    next
+                }
+            }
+            RunState::NextLevel => {
+                self.goto_next_level();
+                RunState::PreRun
+            }
+            RunState::SaveGame => {
+                let message = match saveload_system::save_game(&mut self.ecs) {
+                    Ok(()) => "Game saved.".to_string(),
+                    Err(e) => format!("Save failed: {}", e),
+                };
+                self.ecs
+                    .fetch_mut::<GameLog>()
+                    .add(LogCategory::System, message);
+                RunState::AwaitingInput
+            }
+            RunState::LoadGame => match saveload_system::load_game() {
+                Ok(world) => {
+                    self.ecs = world;
+                    self.ecs
+                        .fetch_mut::<GameLog>()
+                        .add(LogCategory::System, "Game loaded.");
+                    RunState::PreRun
+                }
+                Err(e) => {
+                    self.ecs
+                        .fetch_mut::<GameLog>()
+                        .add(LogCategory::System, format!("Load failed: {}", e));
+                    RunState::AwaitingInput
+                }
+            },
+            _ => runstate,
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn entities_to_remove_on_level_change(&self) -> Vec<Entity> {
+        let entities = self.ecs.entities();
+        let player = self.ecs.read_storage::<Player>();
+        let backpack = self.ecs.read_storage::<InBackpack>();
+        let equipped = self.ecs.read_storage::<Equipped>();
+        let player_entity = self.ecs.fetch::<Entity>();
+
+        // everything goes except the player and what they carry or wear
+        (&entities)
+            .join()
+            .filter(|entity| player.get(*entity).is_none())
+            .filter(|entity| match backpack.get(*entity) {
+                Some(pack) => pack.owner != *player_entity,
+                None => true,
+            })
+            .filter(|entity| match equipped.get(*entity) {
+                Some(item) => item.owner != *player_entity,
+                None => true,
+            })
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn goto_next_level(&mut self) {
+        for target in self.entities_to_remove_on_level_change() {
+            self.ecs
+                .delete_entity(target)
+                .expect("Unable to delete entity");
+        }
+
+        // build the next level with the world's generator
+        let depth = self.ecs.fetch::<Map>().depth + 1;
+        let mut rng = self.ecs.remove::<RandomNumberGenerator>().unwrap();
+        let (map, start) = map_builders::build_level(&mut self.ecs, &mut rng, depth);
+        let (px, py) = (start.x, start.y);
+        self.ecs.insert(rng);
+        self.ecs.insert(map);
+
+        // drop the player at the start of the new level
+        let player_entity = *self.ecs.fetch::<Entity>();
+        *self.ecs.write_resource::<Point>() = Point::new(px, py);
+        if let Some(position) = self.ecs.write_storage::<Position>().get_mut(player_entity) {
+            position.x = px;
+            position.y = py;
+        }
+        if let Some(viewshed) = self.ecs.write_storage::<Viewshed>().get_mut(player_entity) {
+            viewshed.dirty = true;
+        }
+
+        self.ecs.fetch_mut::<GameLog>().add(
+            LogCategory::System,
+            format!("You descend to level {}.", depth),
+        );
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn render(&self, ctx: &mut Rltk, item_menu: Option<&str>) {
+        let map = self.ecs.fetch::<Map>();
+        map.draw_map(&self.ecs, &self.viewport, ctx);
+
+        let positions = self.ecs.read_storage::<Position>();
+        let renderables = self.ecs.read_storage::<Renderable>();
+        let doors = self.ecs.read_storage::<Door>();
+
+        let camera = self.viewport.camera(&self.ecs);
+        let mut data = (&positions, &renderables, doors.maybe())
+            .join()
+            .collect::<Vec<_>>();
+        data.sort_by_key(|&(_pos, render, _door)| std::cmp::Reverse(render.render_order));
+        for (pos, render, door) in data {
+            // doors stay on the map once seen, greyed out like the walls around them
+            let idx = map.xy_idx(pos.x, pos.y);
+            let fg = if map.visible_tiles[idx] {
+                render.fg
+            } else if door.is_some() && map.revealed_tiles[idx] {
+                render.fg.to_greyscale()
+            } else {
+                continue;
+            };
+            if let Some(screen) = camera.to_screen(Point::new(pos.x, pos.y)) {
+                ctx.set(screen.x, screen.y, fg, render.bg, render.glyph);
             }
+        }
+
+        gui::draw_ui(&self.ecs, ctx, &self.viewport, item_menu);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut Rltk) {
+        ctx.cls();
+
+        let mut newrunstate;
+        {
+            let runstate = self.ecs.fetch::<RunState>();
+            newrunstate = *runstate;
+        }
+
+        let (rs, item_menu) = match newrunstate {
+            RunState::AwaitingInput => match replay::next_command(&mut self.ecs, ctx.frame_time_ms)
+            {
+                Some(command) => (perform_command(self, command), None),
+                None => (player_input(self, ctx), None),
+            },
             RunState::ShowInventory => {
                 let result = gui::menu_inventory(self, ctx);
 
                 match result {
-                    gui::ItemMenuResult::Cancel => (RunState::AwaitingInput, false),
-                    gui::ItemMenuResult::NoResponse => (newrunstate, true),
+                    gui::ItemMenuResult::Cancel => (RunState::AwaitingInput, None),
+                    gui::ItemMenuResult::NoResponse => (newrunstate, Some("Inventory")),
                     gui::ItemMenuResult::Selected(entity) => {
-                        let is_ranged = self.ecs.read_storage::<Ranged>();
-                        let is_item_ranged = is_ranged.get(entity);
-                        if let Some(is_item_ranged) = is_item_ranged {
+                        let item_range = self
+                            .ecs
+                            .read_storage::<Ranged>()
+                            .get(entity)
+                            .map(|ranged| ranged.range);
+                        if let Some(range) = item_range {
                             (
                                 RunState::ShowTargeting {
-                                    range: is_item_ranged.range,
+                                    range,
                                     item: entity,
+                                    cursor: *self.ecs.fetch::<Point>(),
                                 },
-                                false,
+                                None,
                             )
                         } else {
-                            let mut intent = self.ecs.write_storage::<WantsToConsumeItem>();
-                            intent
-                                .insert(
-                                    *self.ecs.fetch::<Entity>(),
-                                    WantsToConsumeItem { item: entity },
-                                )
-                                .expect("Unable to insert intent");
-                            (RunState::PlayerTurn, false)
+                            (self.use_item(entity, None), None)
                         }
                     }
                 }
             }
-            RunState::ShowTargeting { range, item } => match gui::ranged_target(self, ctx, range) {
-                gui::ItemTargetingResult::Cancel => (RunState::AwaitingInput, false),
-                gui::ItemTargetingResult::NoResponse => (RunState::AwaitingInput, false),
-                gui::ItemTargetingResult::Targeted(_p) => (RunState::AwaitingInput, false),
+            RunState::ShowDropItem => match gui::menu_drop_item(self, ctx) {
+                gui::ItemMenuResult::Cancel => (RunState::AwaitingInput, None),
+                gui::ItemMenuResult::NoResponse => (newrunstate, Some("Drop which item?")),
+                gui::ItemMenuResult::Selected(entity) => match item_slot(&self.ecs, entity) {
+                    Some(slot) => (
+                        perform_command(self, PlayerCommand::DropItem { slot }),
+                        None,
+                    ),
+                    None => (RunState::AwaitingInput, None),
+                },
             },
+            RunState::ShowTargeting {
+                range,
+                item,
+                cursor,
+            } => match gui::ranged_target(self, ctx, item, range, cursor) {
+                gui::ItemTargetingResult::Cancel => (RunState::AwaitingInput, None),
+                gui::ItemTargetingResult::NoResponse => (newrunstate, None),
+                gui::ItemTargetingResult::MoveCursor(cursor) => (
+                    RunState::ShowTargeting {
+                        range,
+                        item,
+                        cursor,
+                    },
+                    None,
+                ),
+                gui::ItemTargetingResult::Targeted(target) => {
+                    (self.use_item(item, Some(target)), None)
+                }
+            },
+            RunState::MainMenu {
+                selected,
+                seed,
+                load_failed,
+            } => match gui::main_menu(ctx, selected, seed, load_failed) {
+                gui::MainMenuResult::NoSelection { selected, seed } => (
+                    RunState::MainMenu {
+                        selected,
+                        seed,
+                        load_failed,
+                    },
+                    None,
+                ),
+                gui::MainMenuResult::Selected { selected, seed } => match selected {
+                    MainMenuSelection::NewGame => {
+                        self.ecs = new_world(seed.unwrap_or(DEFAULT_SEED));
+                        replay::start_recording(&mut self.ecs, replay::REPLAY_PATH);
+                        (RunState::PreRun, None)
+                    }
+                    MainMenuSelection::Continue => match self.advance(RunState::LoadGame) {
+                        RunState::PreRun => (RunState::PreRun, None),
+                        _ => (
+                            RunState::MainMenu {
+                                selected,
+                                seed,
+                                load_failed: true,
+                            },
+                            None,
+                        ),
+                    },
+                    MainMenuSelection::Quit => {
+                        ctx.quit();
+                        (newrunstate, None)
+                    }
+                },
+            },
+            RunState::ShowLog { page } => {
+                match gui::log_history(&self.ecs, ctx, &self.viewport, page) {
+                    gui::LogHistoryResult::Close => (RunState::AwaitingInput, None),
+                    gui::LogHistoryResult::Page(page) => (RunState::ShowLog { page }, None),
+                }
+            }
+            RunState::MapGeneration { frame, paused } => {
+                match gui::map_generation(&self.ecs, ctx, &self.viewport, frame, paused) {
+                    gui::MapGenResult::Done => {
+                        self.ecs.remove::<MapGenHistory>();
+                        (RunState::PreRun, None)
+                    }
+                    gui::MapGenResult::Frame { frame, paused } => {
+                        (RunState::MapGeneration { frame, paused }, None)
+                    }
+                }
+            }
+            RunState::GameOver => match gui::game_over(&self.ecs, ctx, &self.viewport) {
+                gui::GameOverResult::NoSelection => (newrunstate, None),
+                gui::GameOverResult::NewGame => {
+                    self.new_game();
+                    (RunState::PreRun, None)
+                }
+            },
+            _ => (self.advance(newrunstate), None),
         };
 
         newrunstate = rs;
 
+        // a level that was just dug is shown being built before play starts on it
+        if newrunstate == RunState::PreRun
+            && self.show_mapgen
+            && self.ecs.has_value::<MapGenHistory>()
+        {
+            newrunstate = RunState::MapGeneration {
+                frame: 0,
+                paused: false,
+            };
+        }
+
         {
             let mut runwriter = self.ecs.write_resource::<RunState>();
             *runwriter = newrunstate
         }
 
-        damage_system::delete_the_dead(&mut self.ecs);
-
-        let map = self.ecs.fetch::<Map>();
-        //map.draw_map(&self.ecs, &self.viewport, ctx);
-        map.draw_map(&self.ecs, &self.viewport, ctx);
-
-        let positions = self.ecs.read_storage::<Position>();
-        let renderables = self.ecs.read_storage::<Renderable>();
-
-        let mut data = (&positions, &renderables).join().collect::<Vec<_>>();
-        data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
-        for (pos, render) in data {
-            let idx = map.xy_idx(pos.x, pos.y);
-            if map.visible_tiles[idx] {
-                ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph);
-            }
+        // the last command's turn has played out, so it can be written down or checked
+        if newrunstate == RunState::AwaitingInput || newrunstate == RunState::GameOver {
+            replay::finish_step(&mut self.ecs);
         }
 
-        gui::draw_ui(&self.ecs, ctx, &self.viewport, should_show_inventory);
+        // the menu, log history and game over screens replace the map
+        match newrunstate {
+            RunState::MainMenu { .. }
+            | RunState::ShowLog { .. }
+            | RunState::MapGeneration { .. }
+            | RunState::GameOver => {}
+            _ => self.render(ctx, item_menu),
+        }
     }
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn main() -> rltk::BError {
-    use rltk::RltkBuilder;
-
-    // create world
-    let mut world = World::new();
+pub fn register_components(world: &mut World) {
     world.register::<Position>();
     world.register::<CombatStats>();
     world.register::<Renderable>();
@@ -163,25 +470,55 @@ fn main() -> rltk::BError {
     world.register::<Monster>();
     world.register::<WantsToMelee>();
     world.register::<SufferDamage>();
+    world.register::<Slain>();
+    world.register::<Experience>();
+    world.register::<GrantsXp>();
+    world.register::<PlayerRecord>();
     world.register::<Item>();
     world.register::<Consumable>();
     world.register::<ProvidesHealing>();
     world.register::<InBackpack>();
     world.register::<WantsToPickupItem>();
-    world.register::<WantsToConsumeItem>();
+    world.register::<WantsToUseItem>();
     world.register::<Ranged>();
     world.register::<InflictsDamage>();
+    world.register::<AreaOfEffect>();
+    world.register::<Confusion>();
+    world.register::<Sleep>();
+    world.register::<Poison>();
+    world.register::<Regeneration>();
+    world.register::<StatusEffects>();
+    world.register::<Equippable>();
+    world.register::<Equipped>();
+    world.register::<MeleePowerBonus>();
+    world.register::<DefenseBonus>();
+    world.register::<WantsToRemoveItem>();
+    world.register::<WantsToDropItem>();
+    world.register::<Door>();
+    world.register::<WantsToOpenDoor>();
+    world.register::<WantsToCloseDoor>();
+    world.register::<SimpleMarker<SerializeMe>>();
+    world.register::<SerializationHelper>();
+}
 
-    //world.insert(new_map(&gs));
-    let viewport = Viewport {
-        map_width: 80,
-        map_height: 43,
-        log_height: 7,
-    };
-    //let mut rng = rltk::RandomNumberGenerator::new();
-    let mut rng = rltk::RandomNumberGenerator::seeded(2);
-    let map = Map::new_map_rooms_and_corridors(&mut world, &viewport, &mut rng);
-    let (px, py) = map.rooms[0].center();
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn new_world(seed: u64) -> World {
+    let mut world = World::new();
+    register_components(&mut world);
+    world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+
+    // the log goes in first, so building the level can write to it
+    let mut log = GameLog::default();
+    log.add(LogCategory::System, "Welcome to Rusty Roguelike");
+    log.add(
+        LogCategory::System,
+        format!("This dungeon was generated from seed {}.", seed),
+    );
+    world.insert(log);
+
+    let mut rng = rltk::RandomNumberGenerator::seeded(seed);
+    let (map, start) = map_builders::build_level(&mut world, &mut rng, 1);
+    let (px, py) = (start.x, start.y);
 
     world.insert(map);
 
@@ -190,20 +527,86 @@ fn main() -> rltk::BError {
         world: &mut world,
         rng: &mut rng,
         position: Position { x: px, y: py },
+        depth: 1,
     });
 
+    world.insert(rng);
+    world.insert(GameSeed { seed });
     world.insert(Point::new(px, py));
     world.insert(player_entity);
     world.insert(RunState::PreRun);
-    world.insert(gamelog::GameLog {
-        entries: vec!["Welcome to Rusty Roguelike".to_string()],
-    });
+    world
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(|value| value.as_str())
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// --seed <n> picks the dungeon, --random-seed draws a fresh one; this is the only
+// place a generator is seeded from outside the game, every other roll goes through the seed
+pub fn seed_arg(args: &[String]) -> Result<Option<u64>, std::num::ParseIntError> {
+    if args.iter().any(|arg| arg == "--random-seed") {
+        return Ok(Some(RandomNumberGenerator::new().next_u64()));
+    }
+    arg_value(args, "--seed")
+        .map(|value| value.parse())
+        .transpose()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn main() -> rltk::BError {
+    use rltk::RltkBuilder;
+
+    if let Err(e) = raws::load(raws::RAWS_PATH) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
-    // create game state
-    let gs = State {
-        viewport: viewport,
-        ecs: world,
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(limit) = arg_value(&args, "--log-history") {
+        gamelog::set_history_limit(limit.parse()?);
+    }
+    if let Some(depth) = arg_value(&args, "--spawn-odds") {
+        spawner::print_spawn_odds(depth.parse()?);
+        return Ok(());
+    }
+    if !cfg!(feature = "window") || args.iter().any(|arg| arg == "--headless") {
+        return headless::main(&args);
+    }
+
+    let keys = arg_value(&args, "--keys").unwrap_or(keybindings::KEYBINDINGS_PATH);
+    if let Err(e) = keybindings::load(keys) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    // create game state; the world is replaced once the menu starts or continues a game
+    let seed = seed_arg(&args)?;
+    let show_mapgen = args.iter().any(|arg| arg == "--show-mapgen");
+    map_builders::set_record_snapshots(show_mapgen);
+    let mut gs = State {
+        ecs: new_world(DEFAULT_SEED),
+        viewport: Viewport::default(),
+        show_mapgen,
     };
+    match arg_value(&args, "--replay") {
+        // --replay <file> skips the menu and plays the recording back on screen
+        Some(path) => {
+            let recording = replay::read_recording(path)?;
+            gs.ecs = new_world(recording.seed);
+            replay::start_replay(&mut gs.ecs, recording);
+        }
+        None => gs.ecs.insert(RunState::MainMenu {
+            selected: MainMenuSelection::NewGame,
+            seed,
+            load_failed: false,
+        }),
+    }
 
     // start app
     let mut context = RltkBuilder::simple80x50()
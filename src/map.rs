@@ -1,69 +1,55 @@
-use super::{spawner, Position, Rect, Viewport, Viewshed};
-use rltk::{Algorithm2D, BaseMap, Point, RandomNumberGenerator, Rltk, RGB};
+use super::{Position, Rect, Viewport};
+use rltk::{Algorithm2D, BaseMap, Point, Rltk, RGB};
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 
+// the whole level; only the part around the player fits on screen
+pub const MAP_WIDTH: i32 = 120;
+pub const MAP_HEIGHT: i32 = 64;
+
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
-}
-
-mod map_utils {
-    use super::Rect;
-    use super::TileType;
-    use std::cmp::{max, min};
-
-    // ------------------------------------------------------------------------------------------------------------------ //
-    pub fn apply_horizontal_tunnel(
-        map: &Rect,
-        x1: i32,
-        x2: i32,
-        y: i32,
-        tiles: &mut Vec<TileType>,
-    ) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            let idx = map.xy_idx(x, y);
-            if idx > 0 && idx < map.area() as usize {
-                tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
-    // ------------------------------------------------------------------------------------------------------------------ //
-    pub fn apply_vertical_tunnel(map: &Rect, y1: i32, y2: i32, x: i32, tiles: &mut Vec<TileType>) {
-        for y in min(y1, y2)..=max(y1, y2) {
-            let idx = map.xy_idx(x, y);
-            if idx > 0 && idx < map.area() as usize {
-                tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
-    // ------------------------------------------------------------------------------------------------------------------ //
-    pub fn apply_room_to_map(map: &Rect, room: &Rect, tiles: &mut Vec<TileType>) {
-        for y in room.min.y..room.max.y {
-            for x in room.min.x..room.max.x {
-                let idx = map.xy_idx(x, y);
-                tiles[idx] = TileType::Floor;
-            }
-        }
-    }
+    DownStairs,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
     pub size: Rect,
     pub tiles: Vec<TileType>,
     pub rooms: Vec<Rect>,
+    pub depth: i32,
     pub revealed_tiles: Vec<bool>,
     pub visible_tiles: Vec<bool>,
     pub blocked: Vec<bool>,
+    pub closed_doors: Vec<bool>,
+
+    // rebuilt by the indexing system after a load
+    #[serde(skip)]
     pub tile_content: Vec<Vec<Entity>>,
 }
 
 impl Map {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // solid rock, for a builder to dig out
+    pub fn new(size: Rect) -> Map {
+        let vec_size = size.area() as usize;
+        Map {
+            size,
+            tiles: vec![TileType::Wall; vec_size],
+            rooms: Vec::new(),
+            depth: 0,
+            revealed_tiles: vec![false; vec_size],
+            visible_tiles: vec![false; vec_size],
+            blocked: vec![false; vec_size],
+            closed_doors: vec![false; vec_size],
+            tile_content: vec![Vec::new(); vec_size],
+        }
+    }
+
     // ------------------------------------------------------------------------------------------------------------------ //
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
         self.size.xy_idx(x, y)
@@ -76,17 +62,19 @@ impl Map {
 
     // ------------------------------------------------------------------------------------------------------------------ //
     fn is_exit_valid(&self, x: i32, y: i32) -> bool {
-        if !self.size.contains(&Position { x, y }) {
+        if !self.in_bounds(Point::new(x, y)) {
             return false;
         }
         let idx = self.xy_idx(x, y);
-        !self.blocked[idx]
+        // a closed door is in the way, but paths can still go through it once it's opened
+        !self.blocked[idx] || self.closed_doors[idx]
     }
 
     // ------------------------------------------------------------------------------------------------------------------ //
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
             self.blocked[i] = *tile == TileType::Wall;
+            self.closed_doors[i] = false;
         }
     }
 
@@ -98,148 +86,84 @@ impl Map {
     }
 
     // ------------------------------------------------------------------------------------------------------------------ //
-    pub fn new_map_rooms_and_corridors(
-        world: &mut World,
-        viewport: &Viewport,
-        rng: &mut RandomNumberGenerator,
-    ) -> Map {
-        let size = Rect::new(0, 0, viewport.map_width, viewport.map_height);
-        let mut tiles = vec![TileType::Wall; size.area() as usize];
-        let mut rooms = Vec::new();
-
-        const MAX_ROOMS: i32 = 30;
-        const SIZE_RANGE: (i32, i32) = (6, 10);
-
-        for _ in 0..MAX_ROOMS {
-            let (w, h) = (
-                rng.range(SIZE_RANGE.0, SIZE_RANGE.1),
-                rng.range(SIZE_RANGE.0, SIZE_RANGE.1),
-            );
-            let (x, y) = (
-                rng.range(1, size.width() - w - 1),
-                rng.range(1, size.height() - h - 1),
-            );
-            let new_room = Rect::new(x, y, w, h);
-            let mut ok = true;
-            for other_room in rooms.iter() {
-                if new_room.intersect(other_room) {
-                    ok = false;
-                    break;
-                }
-            }
-            if ok {
-                rooms.push(new_room);
-            }
-        }
-
-        // some test rooms instead:
-        // rooms.push(Rect::new(2, 1, 6, 6));
-        // rooms.push(Rect::new(1, 12, 6, 6));
-
-        dbg!(for room in &rooms {
-            room.print_debug();
-        });
-
-        for (index, room) in rooms.iter().enumerate() {
-            map_utils::apply_room_to_map(&size, &room, &mut tiles);
-
-            if index > 0 {
-                let (new_x, new_y) = room.center();
-                let (prev_x, prev_y) = rooms[index - 1].center();
-                let (tunnel_x, tunnel_y) = if rng.range(0, 2) == 1 {
-                    (new_x, prev_y)
-                } else {
-                    (prev_x, new_y)
-                };
-                map_utils::apply_vertical_tunnel(&size, prev_y, new_y, tunnel_x, &mut tiles);
-                map_utils::apply_horizontal_tunnel(&size, prev_x, new_x, tunnel_y, &mut tiles);
-            }
-
-            spawner::spawn_room(world, rng, &room);
-        }
-
-        let vec_size = size.area() as usize;
-        Map {
-            size,
-            tiles,
-            rooms,
-            revealed_tiles: vec![false; vec_size],
-            visible_tiles: vec![false; vec_size],
-            blocked: vec![false; vec_size],
-            tile_content: vec![Vec::new(); vec_size],
+    fn tile_glyph(tile: TileType) -> (RGB, rltk::FontCharType) {
+        match tile {
+            TileType::Floor => (RGB::from_f32(0.5, 0.5, 0.5), rltk::to_cp437('.')),
+            TileType::Wall => (RGB::from_f32(0.0, 1.0, 0.0), rltk::to_cp437('#')),
+            TileType::DownStairs => (RGB::from_f32(0.0, 1.0, 1.0), rltk::to_cp437('>')),
         }
     }
 
     // ------------------------------------------------------------------------------------------------------------------ //
     fn draw_map_visibility(&self, ecs: &World, viewport: &Viewport, ctx: &mut Rltk) {
-        let mut viewsheds = ecs.write_storage::<Viewshed>();
-
-        let floor = rltk::to_cp437('.');
-        let wall = rltk::to_cp437('#');
-        //let numbers = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+        let camera = viewport.camera(ecs);
         let black = RGB::from_f32(0., 0., 0.);
 
-        for _viewshed in (&mut viewsheds).join() {
-            let mut y = 0 as i32;
-            let mut x = 0 as i32;
-            for (idx, tile) in self.tiles.iter().enumerate() {
-                //render a tile depending on the tile type
+        for y in 0..camera.height {
+            for x in 0..camera.width {
+                let world = camera.to_world(Point::new(x, y)).unwrap();
+                if !self.in_bounds(world) {
+                    continue;
+                }
 
+                //render a tile depending on the tile type
+                let idx = self.xy_idx(world.x, world.y);
                 if self.revealed_tiles[idx] {
-                    let (visible_color, character) = match tile {
-                        TileType::Floor => (RGB::from_f32(0.5, 0.5, 0.5), floor),
-                        TileType::Wall => (RGB::from_f32(0.0, 1.0, 0.0), wall),
-                        // TileType::Wall => (
-                        //     RGB::from_f32(0.0, 1.0, 0.0),
-                        //     rltk::to_cp437(numbers[(x % 10) as usize]),
-                        // ),
-                    };
-
+                    let (visible_color, character) = Map::tile_glyph(self.tiles[idx]);
                     let color = if self.visible_tiles[idx] {
                         visible_color
                     } else {
                         visible_color.to_greyscale()
                     };
-
                     ctx.set(x, y, color, black, character);
                 }
-
-                x += 1;
-                if x > viewport.map_width - 1 {
-                    x = 0;
-                    y += 1;
-                }
             }
         }
     }
 
     // ------------------------------------------------------------------------------------------------------------------ //
+    #[allow(dead_code)]
     fn draw_map_debug(&self, ecs: &World, viewport: &Viewport, ctx: &mut Rltk) {
-        let floor = rltk::to_cp437('.');
-        let wall = rltk::to_cp437('#');
-        //let numbers = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+        let camera = viewport.camera(ecs);
         let black = RGB::from_f32(0., 0., 0.);
 
-        let mut y = 0;
-        let mut x = 0;
-        for tile in self.tiles.iter() {
-            // Render a tile depending upon the tile type
-            let (color, character) = match tile {
-                TileType::Floor => (RGB::from_f32(0.5, 0.5, 0.5), floor),
-                TileType::Wall => (
-                    RGB::from_f32(0.0, 1.0, 0.0),
-                    //rltk::to_cp437(numbers[(x % 10) as usize]),
-                    wall,
-                ),
-            };
-
-            ctx.set(x, y, color, black, character);
+        for y in 0..camera.height {
+            for x in 0..camera.width {
+                let world = camera.to_world(Point::new(x, y)).unwrap();
+                if self.in_bounds(world) {
+                    // Render a tile depending upon the tile type, seen or not
+                    let (color, character) =
+                        Map::tile_glyph(self.tiles[self.xy_idx(world.x, world.y)]);
+                    ctx.set(x, y, color, black, character);
+                }
+            }
+        }
+    }
 
-            // Move the coordinates
-            x += 1;
-            if x > viewport.map_width - 1 {
-                x = 0;
-                y += 1;
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // the whole map squeezed into the viewport, for watching a level being dug. a cell that covers several tiles
+    // shows the most telling of them, so narrow corridors don't vanish
+    pub fn draw_map_overview(&self, viewport: &Viewport, ctx: &mut Rltk) {
+        let black = RGB::from_f32(0., 0., 0.);
+        let (width, height) = (self.size.width(), self.size.height());
+        let columns = i32::min(viewport.map_width, width);
+        let rows = i32::min(viewport.map_height, height);
+
+        for y in 0..rows {
+            for x in 0..columns {
+                let tile = (y * height / rows..(y + 1) * height / rows)
+                    .flat_map(|ty| {
+                        (x * width / columns..(x + 1) * width / columns).map(move |tx| (tx, ty))
+                    })
+                    .map(|(tx, ty)| self.tiles[self.xy_idx(tx, ty)])
+                    .max_by_key(|tile| match tile {
+                        TileType::Wall => 0,
+                        TileType::Floor => 1,
+                        TileType::DownStairs => 2,
+                    })
+                    .unwrap_or(TileType::Wall);
+                let (color, character) = Map::tile_glyph(tile);
+                ctx.set(x, y, color, black, character);
             }
         }
     }
@@ -265,7 +189,7 @@ impl Algorithm2D for Map {
 // ------------------------------------------------------------------------------------------------------------------ //
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] == TileType::Wall
+        self.tiles[idx] == TileType::Wall || self.closed_doors[idx]
     }
 
     // ------------------------------------------------------------------------------------------------------------------ //
@@ -0,0 +1,96 @@
+use super::super::{Map, Position, Rect, TileType};
+use super::{common, BuiltMap, MapBuilder};
+use rltk::RandomNumberGenerator;
+
+// a leaf is only split while both halves can be at least this big
+const MIN_LEAF_SIZE: i32 = 12;
+const ROOM_SIZE_RANGE: (i32, i32) = (5, 12);
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// binary space partitioning: the map is cut in two again and again, and each piece that's left gets one room.
+// rooms never overlap, and neighbouring pieces are joined in the order the cuts made them
+pub struct BspBuilder {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn split(area: Rect, rng: &mut RandomNumberGenerator, leaves: &mut Vec<Rect>) {
+    let can_split_x = area.width() >= MIN_LEAF_SIZE * 2;
+    let can_split_y = area.height() >= MIN_LEAF_SIZE * 2;
+    if !can_split_x && !can_split_y {
+        leaves.push(area);
+        return;
+    }
+
+    let split_x = if can_split_x && can_split_y {
+        rng.range(0, 2) == 0
+    } else {
+        can_split_x
+    };
+    let (x, y) = (area.min.x, area.min.y);
+    let (w, h) = (area.width(), area.height());
+    if split_x {
+        let cut = rng.range(MIN_LEAF_SIZE, w - MIN_LEAF_SIZE + 1);
+        split(Rect::new(x, y, cut, h), rng, leaves);
+        split(Rect::new(x + cut, y, w - cut, h), rng, leaves);
+    } else {
+        let cut = rng.range(MIN_LEAF_SIZE, h - MIN_LEAF_SIZE + 1);
+        split(Rect::new(x, y, w, cut), rng, leaves);
+        split(Rect::new(x, y + cut, w, h - cut), rng, leaves);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// a room somewhere inside the leaf, keeping a wall between it and the leaf's edge
+fn room_in(leaf: &Rect, rng: &mut RandomNumberGenerator) -> Rect {
+    let w = rng.range(
+        ROOM_SIZE_RANGE.0,
+        i32::min(ROOM_SIZE_RANGE.1, leaf.width() - 2) + 1,
+    );
+    let h = rng.range(
+        ROOM_SIZE_RANGE.0,
+        i32::min(ROOM_SIZE_RANGE.1, leaf.height() - 2) + 1,
+    );
+    let x = leaf.min.x + rng.range(1, leaf.width() - w);
+    let y = leaf.min.y + rng.range(1, leaf.height() - h);
+    Rect::new(x, y, w, h)
+}
+
+impl MapBuilder for BspBuilder {
+    fn build(&mut self, size: Rect, rng: &mut RandomNumberGenerator) -> Option<BuiltMap> {
+        let mut map = Map::new(size);
+
+        // the outer ring of the map stays solid
+        let mut leaves = Vec::new();
+        let inner = Rect::new(1, 1, map.size.width() - 2, map.size.height() - 2);
+        split(inner, rng, &mut leaves);
+
+        let rooms: Vec<Rect> = leaves.iter().map(|leaf| room_in(leaf, rng)).collect();
+        let mut snapshots = Vec::new();
+        for (index, room) in rooms.iter().enumerate() {
+            common::apply_room_to_map(&mut map, room);
+            if index > 0 {
+                common::connect_rooms(&mut map, rng, &rooms[index - 1], room);
+            }
+            common::snapshot(&mut snapshots, &map);
+        }
+
+        let (stairs_x, stairs_y) = rooms.last().unwrap().center();
+        let stairs_idx = map.xy_idx(stairs_x, stairs_y);
+        map.tiles[stairs_idx] = TileType::DownStairs;
+        common::snapshot(&mut snapshots, &map);
+
+        let (start_x, start_y) = rooms[0].center();
+        let doors = common::door_positions(&map, &rooms);
+        let spawn_regions = rooms.iter().map(common::room_region).collect();
+        map.rooms = rooms;
+        Some(BuiltMap {
+            map,
+            start: Position {
+                x: start_x,
+                y: start_y,
+            },
+            spawn_regions,
+            doors,
+            snapshots,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use super::super::{Map, Rect, TileType};
+use super::{common, BuiltMap, MapBuilder, SPAWN_REGION_SIZE};
+use rltk::{Algorithm2D, Point, RandomNumberGenerator};
+
+// the odds, out of 100, that a tile starts out as wall
+const WALL_CHANCE: i32 = 45;
+// the first passes also open up walls in wide open areas, so the caves don't end up as one big hall
+const ROUGH_ITERATIONS: i32 = 4;
+const SMOOTH_ITERATIONS: i32 = 3;
+// how many times the caves can come out solid before the builder gives up
+const MAX_ATTEMPTS: i32 = 10;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// caves grown from noise: each pass, a tile becomes wall when most of the tiles around it are walls.
+// the rough passes also wall in tiles with almost no walls near them, which breaks up open ground
+pub struct CellularAutomataBuilder {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// walls within `radius` of a tile, the tile itself included; anything off the map counts as wall
+fn walls_within(map: &Map, x: i32, y: i32, radius: i32) -> i32 {
+    let mut walls = 0;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (nx, ny) = (x + dx, y + dy);
+            if !map.in_bounds(Point::new(nx, ny)) || map.tiles[map.xy_idx(nx, ny)] == TileType::Wall
+            {
+                walls += 1;
+            }
+        }
+    }
+    walls
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// rolls the noise and runs the passes over it, keeping a snapshot after each
+fn grow_caves(size: Rect, rng: &mut RandomNumberGenerator) -> (Map, Vec<Vec<TileType>>) {
+    let mut map = Map::new(size);
+    let (width, height) = (map.size.width(), map.size.height());
+
+    // the outer ring is left as wall throughout
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let idx = map.xy_idx(x, y);
+            if rng.roll_dice(1, 100) > WALL_CHANCE {
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+
+    let mut snapshots = Vec::new();
+    common::snapshot(&mut snapshots, &map);
+    for iteration in 0..ROUGH_ITERATIONS + SMOOTH_ITERATIONS {
+        let rough = iteration < ROUGH_ITERATIONS;
+        let mut next = map.tiles.clone();
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let wall =
+                    walls_within(&map, x, y, 1) >= 5 || (rough && walls_within(&map, x, y, 2) <= 2);
+                next[map.xy_idx(x, y)] = if wall {
+                    TileType::Wall
+                } else {
+                    TileType::Floor
+                };
+            }
+        }
+        map.tiles = next;
+        common::snapshot(&mut snapshots, &map);
+    }
+    (map, snapshots)
+}
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build(&mut self, size: Rect, rng: &mut RandomNumberGenerator) -> Option<BuiltMap> {
+        // if the caves fill in completely the noise is rolled again, but only so many times
+        let (mut map, start, mut snapshots) = (0..MAX_ATTEMPTS).find_map(|_| {
+            let (map, snapshots) = grow_caves(size.clone(), rng);
+            common::central_floor(&map).map(|start| (map, start, snapshots))
+        })?;
+        let stairs = common::farthest_floor(&mut map, start);
+        let stairs_idx = map.position_idx(stairs);
+        map.tiles[stairs_idx] = TileType::DownStairs;
+        common::snapshot(&mut snapshots, &map);
+
+        let spawn_regions = common::grid_regions(&map, SPAWN_REGION_SIZE);
+        Some(BuiltMap {
+            map,
+            start,
+            spawn_regions,
+            doors: Vec::new(),
+            snapshots,
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use super::super::{Map, Position, Rect, TileType};
+use rltk::DijkstraMap;
+use std::cmp::{max, min};
+
+// how far a tile can be from the start and still be found by farthest_floor
+const MAX_DISTANCE: f32 = 10000.0;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// keeps the tiles as they are now for the map generation view, when it's on
+pub fn snapshot(snapshots: &mut Vec<Vec<TileType>>, map: &Map) {
+    if super::record_snapshots() {
+        snapshots.push(map.tiles.clone());
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// tunnels only dig through rock, so one dug late never covers the stairs
+pub fn apply_horizontal_tunnel(map: &mut Map, x1: i32, x2: i32, y: i32) {
+    for x in min(x1, x2)..=max(x1, x2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.size.area() as usize && map.tiles[idx] == TileType::Wall {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn apply_vertical_tunnel(map: &mut Map, y1: i32, y2: i32, x: i32) {
+    for y in min(y1, y2)..=max(y1, y2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.size.area() as usize && map.tiles[idx] == TileType::Wall {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
+    for y in room.min.y..room.max.y {
+        for x in room.min.x..room.max.x {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// joins two rooms with an L-shaped corridor between their centres, turning at a random corner
+pub fn connect_rooms(map: &mut Map, rng: &mut rltk::RandomNumberGenerator, from: &Rect, to: &Rect) {
+    let (new_x, new_y) = to.center();
+    let (prev_x, prev_y) = from.center();
+    let (tunnel_x, tunnel_y) = if rng.range(0, 2) == 1 {
+        (new_x, prev_y)
+    } else {
+        (prev_x, new_y)
+    };
+    apply_vertical_tunnel(map, prev_y, new_y, tunnel_x);
+    apply_horizontal_tunnel(map, prev_x, new_x, tunnel_y);
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the gaps a tunnel cut in the wall ring around a room: a floor tile with wall on both sides along the ring.
+// corners are left out, as a tunnel only ever crosses the ring straight on, and so is a gap right beside a door
+// already placed, where two rooms' rings touch
+pub fn door_positions(map: &Map, rooms: &[Rect]) -> Vec<Position> {
+    let is_wall = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Wall;
+    let is_floor = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Floor;
+
+    let mut doors: Vec<Position> = Vec::new();
+    for room in rooms {
+        let (top, bottom) = (room.min.y - 1, room.max.y);
+        let (left, right) = (room.min.x - 1, room.max.x);
+        let mut candidates = Vec::new();
+        for x in room.min.x..room.max.x {
+            candidates.push((x, top, true));
+            candidates.push((x, bottom, true));
+        }
+        for y in room.min.y..room.max.y {
+            candidates.push((left, y, false));
+            candidates.push((right, y, false));
+        }
+
+        for (x, y, horizontal) in candidates {
+            let flanked = if horizontal {
+                is_wall(x - 1, y) && is_wall(x + 1, y)
+            } else {
+                is_wall(x, y - 1) && is_wall(x, y + 1)
+            };
+            let beside_door = doors
+                .iter()
+                .any(|door| (door.x - x).abs() <= 1 && (door.y - y).abs() <= 1);
+            if is_floor(x, y) && flanked && !beside_door {
+                doors.push(Position { x, y });
+            }
+        }
+    }
+    doors
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// every tile of a room, as a place to spawn things
+pub fn room_region(room: &Rect) -> Vec<Position> {
+    (0..room.area())
+        .map(|idx| room.idx_position(idx as usize))
+        .collect()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// maps without rooms are cut into square cells instead, each cell's floor being one place to spawn things
+pub fn grid_regions(map: &Map, cell_size: i32) -> Vec<Vec<Position>> {
+    let mut regions = Vec::new();
+    for cell_y in (0..map.size.height()).step_by(cell_size as usize) {
+        for cell_x in (0..map.size.width()).step_by(cell_size as usize) {
+            let mut region = Vec::new();
+            for y in cell_y..min(cell_y + cell_size, map.size.height()) {
+                for x in cell_x..min(cell_x + cell_size, map.size.width()) {
+                    if map.tiles[map.xy_idx(x, y)] == TileType::Floor {
+                        region.push(Position { x, y });
+                    }
+                }
+            }
+            if !region.is_empty() {
+                regions.push(region);
+            }
+        }
+    }
+    regions
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the floor tile nearest the middle of the map, for builders that don't have a first room to start in
+pub fn central_floor(map: &Map) -> Option<Position> {
+    let centre = Position {
+        x: map.size.width() / 2,
+        y: map.size.height() / 2,
+    };
+    (0..map.tiles.len())
+        .filter(|idx| map.tiles[*idx] == TileType::Floor)
+        .map(|idx| map.size.idx_position(idx))
+        .min_by_key(|pos| (pos.x - centre.x).pow(2) + (pos.y - centre.y).pow(2))
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the reachable floor tile furthest from the start, which is where cave levels put the way down
+pub fn farthest_floor(map: &mut Map, start: Position) -> Position {
+    map.populate_blocked();
+    let distances = DijkstraMap::new(
+        map.size.width(),
+        map.size.height(),
+        &[map.position_idx(start)],
+        &*map,
+        MAX_DISTANCE,
+    );
+    let farthest = (0..map.tiles.len())
+        .filter(|idx| map.tiles[*idx] == TileType::Floor && distances.map[*idx] < f32::MAX)
+        .max_by(|a, b| distances.map[*a].total_cmp(&distances.map[*b]));
+    farthest.map_or(start, |idx| map.size.idx_position(idx))
+}
@@ -0,0 +1,196 @@
+use super::super::{Map, Player, Position, TileType};
+use super::common;
+use rltk::BaseMap;
+use specs::prelude::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+// unreachable pockets at least this big are tunnelled to rather than filled in
+const MIN_CONNECTED_REGION: usize = 20;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Default, Clone)]
+pub struct ConnectivityStats {
+    pub floor_tiles: usize,
+    pub reachable_tiles: usize,
+    pub regions_connected: usize,
+    pub regions_walled: usize,
+    pub tiles_walled: usize,
+    pub entities_removed: usize,
+}
+
+impl fmt::Display for ConnectivityStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} floor tiles reachable; {} regions joined, {} walled up ({} tiles); {} entities removed",
+            self.reachable_tiles,
+            self.floor_tiles,
+            self.regions_connected,
+            self.regions_walled,
+            self.tiles_walled,
+            self.entities_removed
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// every tile that can be walked to from the starts, going by the same exits the pathing uses
+fn flood_fill(map: &Map, starts: &[usize]) -> Vec<bool> {
+    let mut reached = vec![false; map.tiles.len()];
+    let mut open: VecDeque<usize> = VecDeque::new();
+    for start in starts {
+        reached[*start] = true;
+        open.push_back(*start);
+    }
+    while let Some(idx) = open.pop_front() {
+        for (exit, _cost) in map.get_available_exits(idx) {
+            if !reached[exit] {
+                reached[exit] = true;
+                open.push_back(exit);
+            }
+        }
+    }
+    reached
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the closest tile already reachable, for a tunnel to head for
+fn nearest_reachable(map: &Map, reachable: &[bool], from: Position) -> Option<Position> {
+    (0..map.tiles.len())
+        .filter(|idx| reachable[*idx])
+        .map(|idx| map.size.idx_position(idx))
+        .min_by_key(|pos| (pos.x - from.x).pow(2) + (pos.y - from.y).pow(2))
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// makes every floor tile reachable from the start: big pockets and the stairs get a tunnel to the nearest
+// reachable tile, small pockets are filled back in. returns which tiles can be reached afterwards
+pub fn connect_or_cull(map: &mut Map, start: Position) -> (Vec<bool>, ConnectivityStats) {
+    let mut stats = ConnectivityStats::default();
+    let start_idx = map.position_idx(start);
+
+    map.populate_blocked();
+    let reachable = flood_fill(map, &[start_idx]);
+    let mut seen = reachable.clone();
+    let mut walled = Vec::new();
+    for idx in 0..map.tiles.len() {
+        if seen[idx] || map.tiles[idx] == TileType::Wall {
+            continue;
+        }
+        // nothing in a pocket can reach outside it, so a fill from any of its tiles finds just the pocket
+        let pocket: Vec<usize> = flood_fill(map, &[idx])
+            .iter()
+            .enumerate()
+            .filter(|(_idx, reached)| **reached)
+            .map(|(idx, _reached)| idx)
+            .collect();
+        for tile in pocket.iter() {
+            seen[*tile] = true;
+        }
+
+        let has_stairs = pocket
+            .iter()
+            .any(|tile| map.tiles[*tile] == TileType::DownStairs);
+        if pocket.len() < MIN_CONNECTED_REGION && !has_stairs {
+            walled.push(pocket);
+            continue;
+        }
+        let from = map.size.idx_position(idx);
+        if let Some(to) = nearest_reachable(map, &reachable, from) {
+            common::apply_horizontal_tunnel(map, from.x, to.x, from.y);
+            common::apply_vertical_tunnel(map, from.y, to.y, to.x);
+            stats.regions_connected += 1;
+        }
+    }
+
+    // fill in the small pockets, unless one of the tunnels happened to open it up
+    map.populate_blocked();
+    let reachable = flood_fill(map, &[start_idx]);
+    for pocket in walled {
+        if pocket.iter().any(|tile| reachable[*tile]) {
+            continue;
+        }
+        stats.regions_walled += 1;
+        stats.tiles_walled += pocket.len();
+        for tile in pocket {
+            map.tiles[tile] = TileType::Wall;
+        }
+    }
+    map.populate_blocked();
+
+    stats.floor_tiles = map
+        .tiles
+        .iter()
+        .filter(|tile| **tile != TileType::Wall)
+        .count()
+        + stats.tiles_walled;
+    stats.reachable_tiles = (0..map.tiles.len())
+        .filter(|idx| reachable[*idx] && map.tiles[*idx] != TileType::Wall)
+        .count();
+    (reachable, stats)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// deletes anything spawned where the player can't get to; the player is left alone, as they arrive afterwards
+pub fn remove_unreachable(world: &mut World, map: &Map, reachable: &[bool]) -> usize {
+    let out_of_reach: Vec<Entity> = {
+        let entities = world.entities();
+        let positions = world.read_storage::<Position>();
+        let players = world.read_storage::<Player>();
+        (&entities, &positions, !&players)
+            .join()
+            .filter(|(_entity, pos, _player)| {
+                let idx = map.position_idx(**pos);
+                !reachable[idx] || map.tiles[idx] == TileType::Wall
+            })
+            .map(|(entity, _pos, _player)| entity)
+            .collect()
+    };
+    for entity in out_of_reach.iter() {
+        world
+            .delete_entity(*entity)
+            .expect("Unable to delete entity");
+    }
+    out_of_reach.len()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[cfg(test)]
+mod tests {
+    use super::super::super::Rect;
+    use super::*;
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn sealed_pockets_are_joined_or_walled_up() {
+        let mut map = Map::new(Rect::new(0, 0, 30, 12));
+        common::apply_room_to_map(&mut map, &Rect::new(1, 1, 5, 5));
+        // big enough to be joined up, and a lone tile that gets filled in
+        common::apply_room_to_map(&mut map, &Rect::new(12, 2, 6, 5));
+        common::apply_room_to_map(&mut map, &Rect::new(26, 9, 1, 1));
+        // too small to keep, but it holds the way down
+        common::apply_room_to_map(&mut map, &Rect::new(22, 9, 2, 1));
+        let stairs = map.xy_idx(22, 9);
+        map.tiles[stairs] = TileType::DownStairs;
+
+        let start = Position { x: 2, y: 2 };
+        let (reachable, stats) = connect_or_cull(&mut map, start);
+        assert_eq!(stats.regions_connected, 2);
+        assert_eq!(stats.regions_walled, 1);
+        assert_eq!(stats.tiles_walled, 1);
+        assert!(map.tiles[map.xy_idx(26, 9)] == TileType::Wall);
+        assert!(map.tiles[stairs] == TileType::DownStairs);
+
+        let walkable = flood_fill(&map, &[map.position_idx(start)]);
+        for idx in 0..map.tiles.len() {
+            let floor = map.tiles[idx] != TileType::Wall;
+            assert_eq!(floor, walkable[idx], "tile {}", idx);
+            assert_eq!(floor, reachable[idx], "tile {}", idx);
+        }
+        assert_eq!(
+            stats.reachable_tiles,
+            stats.floor_tiles - stats.tiles_walled
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use super::super::{Map, Position, Rect, TileType};
+use super::{common, BuiltMap, MapBuilder, SPAWN_REGION_SIZE};
+use rltk::RandomNumberGenerator;
+
+// digging stops once this share of the map, out of 100, is floor
+const FLOOR_PERCENT: usize = 40;
+const STEPS_PER_DIGGER: i32 = 400;
+// a cap on diggers, in case the walks keep going over old ground
+const MAX_DIGGERS: i32 = 500;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// diggers set off one after another and stagger about at random, each from a floor tile already dug,
+// so every tile they open is joined to the start
+pub struct DrunkardsWalkBuilder {}
+
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build(&mut self, size: Rect, rng: &mut RandomNumberGenerator) -> Option<BuiltMap> {
+        let mut map = Map::new(size);
+        let (width, height) = (map.size.width(), map.size.height());
+
+        let start = Position {
+            x: width / 2,
+            y: height / 2,
+        };
+        let start_idx = map.position_idx(start);
+        map.tiles[start_idx] = TileType::Floor;
+
+        let wanted = map.tiles.len() * FLOOR_PERCENT / 100;
+        let mut floor = vec![start];
+        let mut snapshots = Vec::new();
+        for _ in 0..MAX_DIGGERS {
+            if floor.len() >= wanted {
+                break;
+            }
+            let mut digger = floor[rng.range(0, floor.len() as i32) as usize];
+            for _ in 0..STEPS_PER_DIGGER {
+                let idx = map.position_idx(digger);
+                if map.tiles[idx] == TileType::Wall {
+                    map.tiles[idx] = TileType::Floor;
+                    floor.push(digger);
+                }
+                // the outer ring is never dug
+                match rng.roll_dice(1, 4) {
+                    1 if digger.x > 1 => digger.x -= 1,
+                    2 if digger.x < width - 2 => digger.x += 1,
+                    3 if digger.y > 1 => digger.y -= 1,
+                    4 if digger.y < height - 2 => digger.y += 1,
+                    _ => {}
+                }
+            }
+            common::snapshot(&mut snapshots, &map);
+        }
+
+        let stairs = common::farthest_floor(&mut map, start);
+        let stairs_idx = map.position_idx(stairs);
+        map.tiles[stairs_idx] = TileType::DownStairs;
+        common::snapshot(&mut snapshots, &map);
+
+        let spawn_regions = common::grid_regions(&map, SPAWN_REGION_SIZE);
+        Some(BuiltMap {
+            map,
+            start,
+            spawn_regions,
+            doors: Vec::new(),
+            snapshots,
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use super::{
+    raws, spawner, GameLog, LogCategory, Map, Position, Rect, TileType, MAP_HEIGHT, MAP_WIDTH,
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+use std::sync::OnceLock;
+
+mod bsp;
+mod cellular_automata;
+mod common;
+mod connectivity;
+mod drunkards_walk;
+mod rooms_and_corridors;
+use bsp::BspBuilder;
+use cellular_automata::CellularAutomataBuilder;
+pub use connectivity::ConnectivityStats;
+use drunkards_walk::DrunkardsWalkBuilder;
+use rooms_and_corridors::RoomsAndCorridorsBuilder;
+
+// maps without rooms spawn things in square cells this many tiles across
+const SPAWN_REGION_SIZE: i32 = 16;
+
+// the names the raws use to pick a builder for a level
+pub const BUILDER_NAMES: &[&str] = &[
+    "rooms_and_corridors",
+    "bsp",
+    "cellular_automata",
+    "drunkards_walk",
+];
+
+static RECORD_SNAPSHOTS: OnceLock<bool> = OnceLock::new();
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// set once at startup when the map generation view is on; otherwise builders keep no snapshots
+pub fn set_record_snapshots(record: bool) {
+    let _ = RECORD_SNAPSHOTS.set(record);
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn record_snapshots() -> bool {
+    *RECORD_SNAPSHOTS.get().unwrap_or(&false)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// what a builder hands back: the dug-out map, where the player starts, and where things go.
+// each spawn region gets its own roll of monsters and items, the way a room does.
+// the snapshots are the tiles after each step of the build, ending with the finished map, if they're being recorded
+pub struct BuiltMap {
+    pub map: Map,
+    pub start: Position,
+    pub spawn_regions: Vec<Vec<Position>>,
+    pub doors: Vec<Position>,
+    pub snapshots: Vec<Vec<TileType>>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// how the current level was dug, kept for the map generation viewer; not saved
+pub struct MapGenHistory {
+    pub builder: String,
+    pub snapshots: Vec<Vec<TileType>>,
+    pub stats: ConnectivityStats,
+    pub timer: f32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// builders only lay out tiles; nothing is spawned until the level is put into the world.
+// a builder that can't make a usable map gives up, and the level is dug by rooms and corridors instead
+pub trait MapBuilder {
+    fn build(&mut self, size: Rect, rng: &mut RandomNumberGenerator) -> Option<BuiltMap>;
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn builder(name: &str) -> Option<Box<dyn MapBuilder>> {
+    let builder: Box<dyn MapBuilder> = match name {
+        "rooms_and_corridors" => Box::new(RoomsAndCorridorsBuilder {}),
+        "bsp" => Box::new(BspBuilder {}),
+        "cellular_automata" => Box::new(CellularAutomataBuilder {}),
+        "drunkards_walk" => Box::new(DrunkardsWalkBuilder {}),
+        _ => return None,
+    };
+    Some(builder)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// digs a new level and fills it, noting how it came out in the log; returns the map and where the player starts on it
+pub fn build_level(
+    world: &mut World,
+    rng: &mut RandomNumberGenerator,
+    depth: i32,
+) -> (Map, Position) {
+    // which builder digs a level is rolled from the raws, like a monster would be
+    let table = raws::get().map_builder_table();
+    let mut name = table
+        .roll(rng, depth)
+        .unwrap_or(BUILDER_NAMES[0])
+        .to_string();
+    let mut builder = builder(&name).unwrap_or_else(|| Box::new(RoomsAndCorridorsBuilder {}));
+    let size = Rect::new(0, 0, MAP_WIDTH, MAP_HEIGHT);
+    let built = match builder.build(size.clone(), rng) {
+        Some(built) => built,
+        None => {
+            name = BUILDER_NAMES[0].to_string();
+            RoomsAndCorridorsBuilder {}
+                .build(size, rng)
+                .expect("rooms and corridors always make a map")
+        }
+    };
+    let BuiltMap {
+        mut map,
+        start,
+        spawn_regions,
+        doors,
+        mut snapshots,
+    } = built;
+    map.depth = depth;
+
+    // no builder promises every tile can be walked to, so anything cut off is joined up or filled in
+    let (reachable, mut stats) = connectivity::connect_or_cull(&mut map, start);
+    common::snapshot(&mut snapshots, &map);
+
+    for region in spawn_regions.iter() {
+        spawner::spawn_region(world, rng, region, depth);
+    }
+    for door in doors {
+        let idx = map.position_idx(door);
+        if !reachable[idx] || map.tiles[idx] == TileType::Wall {
+            continue;
+        }
+        spawner::door(world, door);
+        map.closed_doors[idx] = true;
+    }
+    stats.entities_removed = connectivity::remove_unreachable(world, &map, &reachable);
+    world.fetch_mut::<GameLog>().add(
+        LogCategory::System,
+        format!("Level {} ({}): {}.", depth, name, stats),
+    );
+
+    world.insert(MapGenHistory {
+        builder: name,
+        snapshots,
+        stats,
+        timer: 0.0,
+    });
+    (map, start)
+}
@@ -0,0 +1,73 @@
+use super::super::{Map, Position, Rect, TileType};
+use super::{common, BuiltMap, MapBuilder};
+use rltk::RandomNumberGenerator;
+
+const MAX_ROOMS: i32 = 60;
+const SIZE_RANGE: (i32, i32) = (6, 10);
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// rooms dropped at random wherever they fit, each joined to the one dug before it
+pub struct RoomsAndCorridorsBuilder {}
+
+impl MapBuilder for RoomsAndCorridorsBuilder {
+    fn build(&mut self, size: Rect, rng: &mut RandomNumberGenerator) -> Option<BuiltMap> {
+        let mut map = Map::new(size);
+        let mut rooms: Vec<Rect> = Vec::new();
+
+        for _ in 0..MAX_ROOMS {
+            let (w, h) = (
+                rng.range(SIZE_RANGE.0, SIZE_RANGE.1),
+                rng.range(SIZE_RANGE.0, SIZE_RANGE.1),
+            );
+            let (x, y) = (
+                rng.range(1, map.size.width() - w - 1),
+                rng.range(1, map.size.height() - h - 1),
+            );
+            let new_room = Rect::new(x, y, w, h);
+            let mut ok = true;
+            for other_room in rooms.iter() {
+                if new_room.intersect(other_room) {
+                    ok = false;
+                    break;
+                }
+            }
+            if ok {
+                rooms.push(new_room);
+            }
+        }
+
+        // some test rooms instead:
+        // rooms.push(Rect::new(2, 1, 6, 6));
+        // rooms.push(Rect::new(1, 12, 6, 6));
+
+        let mut snapshots = Vec::new();
+        for (index, room) in rooms.iter().enumerate() {
+            common::apply_room_to_map(&mut map, room);
+            if index > 0 {
+                common::connect_rooms(&mut map, rng, &rooms[index - 1], room);
+            }
+            common::snapshot(&mut snapshots, &map);
+        }
+
+        // the way down is always in the last room dug
+        let (stairs_x, stairs_y) = rooms.last().unwrap().center();
+        let stairs_idx = map.xy_idx(stairs_x, stairs_y);
+        map.tiles[stairs_idx] = TileType::DownStairs;
+        common::snapshot(&mut snapshots, &map);
+
+        let (start_x, start_y) = rooms[0].center();
+        let doors = common::door_positions(&map, &rooms);
+        let spawn_regions = rooms.iter().map(common::room_region).collect();
+        map.rooms = rooms;
+        Some(BuiltMap {
+            map,
+            start: Position {
+                x: start_x,
+                y: start_y,
+            },
+            spawn_regions,
+            doors,
+            snapshots,
+        })
+    }
+}
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{Map,Position,BlocksTile};
+use super::{Map,Position,BlocksTile,Door};
 
 pub struct MapIndexingSystem {}
 
@@ -7,10 +7,11 @@ impl<'a> System<'a> for MapIndexingSystem {
     type SystemData = ( WriteExpect<'a,Map>,
                         ReadStorage<'a,Position>,
                         ReadStorage<'a,BlocksTile>,
+                        ReadStorage<'a,Door>,
                         Entities<'a>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, positions, blockers, entities) = data;
+        let (mut map, positions, blockers, doors, entities) = data;
 
         map.populate_blocked();
         map.clear_content_index();
@@ -23,6 +24,12 @@ impl<'a> System<'a> for MapIndexingSystem {
                 map.blocked[idx] = true;
             }
 
+            // a closed door blocks both the way and the view
+            if doors.get(entity).is_some_and(|door| !door.open) {
+                map.blocked[idx] = true;
+                map.closed_doors[idx] = true;
+            }
+
             // add the entity to the tile (it's a copy type so no clone needed)
             map.tile_content[idx].push(entity);
         }
@@ -1,31 +1,43 @@
 use specs::prelude::*;
-use super::{CombatStats,WantsToMelee,Name,SufferDamage,GameLog};
+use super::{CombatStats,WantsToMelee,Name,SufferDamage,GameLog,LogCategory,Equipped,MeleePowerBonus,DefenseBonus};
 
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = ( Entities<'a>,
                         WriteExpect<'a, GameLog>,
                         WriteStorage<'a, WantsToMelee>,
                         ReadStorage<'a, Name>,
                         ReadStorage<'a, CombatStats>,
-                        WriteStorage<'a, SufferDamage>
+                        WriteStorage<'a, SufferDamage>,
+                        ReadStorage<'a, Equipped>,
+                        ReadStorage<'a, MeleePowerBonus>,
+                        ReadStorage<'a, DefenseBonus>
                     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut log, mut wants_to_melee, names, combat_stats, mut inflict_damage) = data;
+        let (entities, mut log, mut wants_to_melee, names, combat_stats, mut inflict_damage, equipped, melee_bonus, defense_bonus) = data;
 
-        for (_entity, wants_melee, name, stats) in (&entities, &wants_to_melee, &names, &combat_stats).join() {
+        for (entity, wants_melee, name, stats) in (&entities, &wants_to_melee, &names, &combat_stats).join() {
             if stats.hp > 0 {
                 let target_stats = combat_stats.get(wants_melee.target).unwrap();
                 if target_stats.hp > 0 {
                     let target_name = names.get(wants_melee.target).unwrap();
-                    let damage = i32::max(0, stats.power - target_stats.defense);
+                    let power_bonus : i32 = (&melee_bonus, &equipped).join()
+                        .filter(|(_bonus, worn)| worn.owner == entity)
+                        .map(|(bonus, _worn)| bonus.power)
+                        .sum();
+                    let armour_bonus : i32 = (&defense_bonus, &equipped).join()
+                        .filter(|(_bonus, worn)| worn.owner == wants_melee.target)
+                        .map(|(bonus, _worn)| bonus.defense)
+                        .sum();
+                    let damage = i32::max(0, (stats.power + power_bonus) - (target_stats.defense + armour_bonus));
                     if damage == 0 {
-                        log.entries.push(format!("{} is unable to hurt {}", &name.name, &target_name.name));
+                        log.add(LogCategory::Combat, format!("{} is unable to hurt {}", &name.name, &target_name.name));
                     } else {
-                        log.entries.push(format!("{} hits {} for {} dmg", &name.name, &target_name.name, damage));
-                        SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage);
+                        log.add(LogCategory::Combat, format!("{} hits {} for {} dmg", &name.name, &target_name.name, damage));
+                        SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage, Some(entity));
                     }
                 }
             }
@@ -1,13 +1,27 @@
 use super::{
-    range, CombatStats, GameLog, Item, Map, Player, Position, RunState, State, Viewshed,
-    WantsToMelee, WantsToPickupItem,
+    inventory_system::inventory_items,
+    keybindings, range, replay,
+    status_system::{has_status, random_direction},
+    Activity, CombatStats, Door, Equipped, GameLog, Item, LogCategory, Map, Monster, Name, Player,
+    PlayerRecord, Position, Ranged, RunState, State, StatusKind, TileType, Viewshed,
+    WantsToCloseDoor, WantsToDropItem, WantsToMelee, WantsToOpenDoor, WantsToPickupItem,
+    WantsToRemoveItem, WantsToUseItem,
 };
-use rltk::{Point, Rltk, VirtualKeyCode};
+use rltk::{Algorithm2D, BaseMap, DijkstraMap, Point, RandomNumberGenerator, Rltk};
+
+// how far away an unrevealed tile can be and still draw an explore
+const MAX_EXPLORE_DISTANCE: f32 = 1000.0;
 use specs::prelude::*;
 
 // ------------------------------------------------------------------------------------------------------------------ //
 fn try_move_player(delta_x: i32, delta_y: i32, gs: &State) {
     let ecs = &gs.ecs;
+    // a confused player stumbles off in a random direction instead
+    let (delta_x, delta_y) = if has_status(ecs, *ecs.fetch::<Entity>(), StatusKind::Confusion) {
+        random_direction(&mut ecs.write_resource::<RandomNumberGenerator>())
+    } else {
+        (delta_x, delta_y)
+    };
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
@@ -16,6 +30,8 @@ fn try_move_player(delta_x: i32, delta_y: i32, gs: &State) {
     // let names = ecs.read_storage::<Name>();
     let entities = ecs.entities();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
+    let doors = ecs.read_storage::<Door>();
+    let mut wants_to_open = ecs.write_storage::<WantsToOpenDoor>();
     let map = ecs.fetch::<Map>();
 
     for (entity, _player, pos, viewshed) in
@@ -47,6 +63,17 @@ fn try_move_player(delta_x: i32, delta_y: i32, gs: &State) {
             }
         }
 
+        // walking into a closed door opens it, which takes the turn
+        let closed_door = map.tile_content[destination_idx]
+            .iter()
+            .find(|door| doors.get(**door).is_some_and(|door| !door.open));
+        if let Some(door) = closed_door {
+            wants_to_open
+                .insert(entity, WantsToOpenDoor { door: *door })
+                .expect("Unable to insert intent");
+            return;
+        }
+
         // no target found, try moving
         if !map.blocked[destination_idx] {
             pos.x = nx;
@@ -75,9 +102,7 @@ fn get_item(ecs: &mut World) {
     }
 
     match target_item {
-        None => gamelog
-            .entries
-            .push("There is nothing here to pick up.".to_string()),
+        None => gamelog.add(LogCategory::Item, "There is nothing here to pick up."),
         Some(item) => {
             let mut pickup = ecs.write_storage::<WantsToPickupItem>();
             pickup
@@ -94,41 +119,355 @@ fn get_item(ecs: &mut World) {
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-enum PlayerMove {
+// closes the first open door next to the player that has nothing standing or lying in the doorway
+fn close_door(ecs: &mut World) -> bool {
+    let player_pos = *ecs.fetch::<Point>();
+    let player_entity = *ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let doors = ecs.read_storage::<Door>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    let mut blocked = false;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (x, y) = (player_pos.x + dx, player_pos.y + dy);
+            if (dx, dy) == (0, 0) || !map.in_bounds(Point::new(x, y)) {
+                continue;
+            }
+            let content = &map.tile_content[map.xy_idx(x, y)];
+            let door = content
+                .iter()
+                .find(|door| doors.get(**door).is_some_and(|door| door.open));
+            if let Some(door) = door {
+                if content.len() > 1 {
+                    blocked = true;
+                    continue;
+                }
+                ecs.write_storage::<WantsToCloseDoor>()
+                    .insert(player_entity, WantsToCloseDoor { door: *door })
+                    .expect("Unable to insert intent");
+                return true;
+            }
+        }
+    }
+
+    if blocked {
+        gamelog.add(LogCategory::System, "Something is in the way.");
+    } else {
+        gamelog.add(LogCategory::System, "There is no open door next to you.");
+    }
+    false
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the entities of one kind standing on tiles the player can see
+fn in_view<T: Component>(ecs: &World) -> Vec<Entity> {
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let kinds = ecs.read_storage::<T>();
+    let positions = ecs.read_storage::<Position>();
+    (&entities, &kinds, &positions)
+        .join()
+        .filter(|(_entity, _kind, pos)| map.visible_tiles[map.position_idx(**pos)])
+        .map(|(entity, _kind, _pos)| entity)
+        .collect()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// catching your breath only works with nothing hostile in sight
+fn wait_turn(ecs: &mut World) {
+    if !in_view::<Monster>(ecs).is_empty() {
+        return;
+    }
+    let player_entity = *ecs.fetch::<Entity>();
+    if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(player_entity) {
+        stats.hp = i32::min(stats.max_hp, stats.hp + 1);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// one step toward the nearest unrevealed tile, over whatever the pathing says is walkable
+fn explore_direction(ecs: &World) -> Option<(i32, i32)> {
+    let map = ecs.fetch::<Map>();
+    let player_pos = *ecs.fetch::<Point>();
+    let starts: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| !map.revealed_tiles[*idx])
+        .collect();
+    if starts.is_empty() {
+        return None;
+    }
+
+    let distances = DijkstraMap::new(
+        map.size.width(),
+        map.size.height(),
+        &starts,
+        &*map,
+        MAX_EXPLORE_DISTANCE,
+    );
+    let here = map.xy_idx(player_pos.x, player_pos.y);
+    map.get_available_exits(here)
+        .iter()
+        .map(|(idx, _cost)| *idx)
+        .filter(|idx| distances.map[*idx] < f32::MAX)
+        .min_by(|a, b| distances.map[*a].total_cmp(&distances.map[*b]))
+        .map(|idx| {
+            let step = map.size.idx_position(idx);
+            (step.x - player_pos.x, step.y - player_pos.y)
+        })
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// chooses the player's move for one turn of a rest or an explore; false once the activity is over
+pub fn activity_step(gs: &mut State, activity: Activity) -> bool {
+    let ecs = &mut gs.ecs;
+    if let Some(monster) = in_view::<Monster>(ecs).first() {
+        let message = format!(
+            "{} is in view.",
+            ecs.read_storage::<Name>().get(*monster).unwrap().name
+        );
+        ecs.fetch_mut::<GameLog>().add(LogCategory::System, message);
+        return false;
+    }
+
+    match activity {
+        Activity::Rest => {
+            let player_entity = *ecs.fetch::<Entity>();
+            let rested = ecs
+                .read_storage::<CombatStats>()
+                .get(player_entity)
+                .is_none_or(|stats| stats.hp >= stats.max_hp);
+            if rested {
+                ecs.fetch_mut::<GameLog>()
+                    .add(LogCategory::System, "You feel rested.");
+                return false;
+            }
+            wait_turn(ecs);
+            true
+        }
+        Activity::Explore => match explore_direction(ecs) {
+            None => {
+                ecs.fetch_mut::<GameLog>()
+                    .add(LogCategory::System, "There is nothing left to explore.");
+                false
+            }
+            Some((dx, dy)) => {
+                try_move_player(dx, dy, gs);
+                true
+            }
+        },
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// what an explore has to stop for: an item that wasn't in view before the last step
+pub fn items_in_view(ecs: &World) -> Vec<Entity> {
+    in_view::<Item>(ecs)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn activity_interrupted(ecs: &mut World, activity: Activity, items_before: &[Entity]) -> bool {
+    if activity != Activity::Explore {
+        return false;
+    }
+    let spotted = items_in_view(ecs)
+        .into_iter()
+        .find(|item| !items_before.contains(item));
+    match spotted {
+        None => false,
+        Some(item) => {
+            let message = format!(
+                "You spot a {}.",
+                ecs.read_storage::<Name>().get(item).unwrap().name
+            );
+            ecs.fetch_mut::<GameLog>().add(LogCategory::Item, message);
+            true
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn try_descend(ecs: &mut World) -> bool {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let idx = map.xy_idx(player_pos.x, player_pos.y);
+    if map.tiles[idx] == TileType::DownStairs {
+        true
+    } else {
+        ecs.fetch_mut::<GameLog>()
+            .add(LogCategory::System, "There is no way down from here.");
+        false
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// using something already equipped takes it off again
+fn use_item(ecs: &mut World, item: Entity, target: Option<Point>) {
+    let player_entity = ecs.fetch::<Entity>();
+    if ecs.read_storage::<Equipped>().get(item).is_some() {
+        let mut intent = ecs.write_storage::<WantsToRemoveItem>();
+        intent
+            .insert(*player_entity, WantsToRemoveItem { item })
+            .expect("Unable to insert intent");
+    } else {
+        let mut intent = ecs.write_storage::<WantsToUseItem>();
+        intent
+            .insert(*player_entity, WantsToUseItem { item, target })
+            .expect("Unable to insert intent");
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn drop_item(ecs: &mut World, item: Entity) {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut intent = ecs.write_storage::<WantsToDropItem>();
+    intent
+        .insert(*player_entity, WantsToDropItem { item })
+        .expect("Unable to insert intent");
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn backpack_slot(ecs: &World, slot: usize) -> Option<Entity> {
+    // slots are numbered in the same order the inventory menu lists them
+    let player_entity = ecs.fetch::<Entity>();
+    inventory_items(ecs, *player_entity)
+        .get(slot)
+        .map(|(item, _equipped)| *item)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the inverse of backpack_slot, so a menu pick can go through perform_command like a typed command
+pub fn item_slot(ecs: &World, item: Entity) -> Option<usize> {
+    let player_entity = ecs.fetch::<Entity>();
+    inventory_items(ecs, *player_entity)
+        .iter()
+        .position(|(candidate, _equipped)| *candidate == item)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn use_backpack_slot(ecs: &mut World, slot: usize, target: Option<Point>) -> bool {
+    match backpack_slot(ecs, slot) {
+        None => false,
+        // a ranged item needs somewhere to aim, or it would go off on the player
+        Some(item) if target.is_none() && ecs.read_storage::<Ranged>().get(item).is_some() => {
+            ecs.fetch_mut::<GameLog>()
+                .add(LogCategory::Item, "That needs a target.");
+            false
+        }
+        Some(item) => {
+            use_item(ecs, item, target);
+            true
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn drop_backpack_slot(ecs: &mut World, slot: usize) -> bool {
+    match backpack_slot(ecs, slot) {
+        None => false,
+        Some(item) => {
+            drop_item(ecs, item);
+            true
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(PartialEq, Copy, Clone)]
+pub enum PlayerCommand {
     Move(i32, i32),
     GetItem,
+    Descend,
+    Wait,
+    Rest,
+    Explore,
+    CloseDoor,
+    UseItem { slot: usize, target: Option<Point> },
+    DropItem { slot: usize },
     RunState(RunState),
     None,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    // player movement
-    match ctx.key {
-        Some(key) => {
-            let player_move = match key {
-                VirtualKeyCode::Left => PlayerMove::Move(-1, 0),
-                VirtualKeyCode::Right => PlayerMove::Move(1, 0),
-                VirtualKeyCode::Up => PlayerMove::Move(0, -1),
-                VirtualKeyCode::Down => PlayerMove::Move(0, 1),
-                VirtualKeyCode::G => PlayerMove::GetItem,
-                VirtualKeyCode::I => PlayerMove::RunState(RunState::ShowInventory),
-                _ => PlayerMove::None,
-            };
-
-            match player_move {
-                PlayerMove::Move(dx, dy) => {
-                    try_move_player(dx, dy, gs);
-                    RunState::PlayerTurn
-                }
-                PlayerMove::GetItem => {
-                    get_item(&mut gs.ecs);
-                    RunState::PlayerTurn
-                }
-                PlayerMove::RunState(state) => state,
-                _ => RunState::AwaitingInput,
+pub fn perform_command(gs: &mut State, command: PlayerCommand) -> RunState {
+    replay::note_command(&mut gs.ecs, &command);
+
+    // whatever gets logged from here until the next command belongs to the turn this one starts
+    let player_entity = *gs.ecs.fetch::<Entity>();
+    let turn = gs
+        .ecs
+        .read_storage::<PlayerRecord>()
+        .get(player_entity)
+        .map_or(0, |record| record.turns);
+    gs.ecs.fetch_mut::<GameLog>().turn = turn + 1;
+
+    // asleep, anything but saving, loading or reading the log just lets the turn pass
+    let wakeful = matches!(
+        command,
+        PlayerCommand::None
+            | PlayerCommand::RunState(RunState::SaveGame)
+            | PlayerCommand::RunState(RunState::LoadGame)
+            | PlayerCommand::RunState(RunState::ShowLog { .. })
+    );
+    if !wakeful && has_status(&gs.ecs, player_entity, StatusKind::Sleep) {
+        gs.ecs
+            .fetch_mut::<GameLog>()
+            .add(LogCategory::System, "You are asleep.");
+        return RunState::PlayerTurn;
+    }
+
+    match command {
+        PlayerCommand::Move(dx, dy) => {
+            try_move_player(dx, dy, gs);
+            RunState::PlayerTurn
+        }
+        PlayerCommand::Wait => {
+            wait_turn(&mut gs.ecs);
+            RunState::PlayerTurn
+        }
+        PlayerCommand::Rest => RunState::Activity(Activity::Rest),
+        PlayerCommand::Explore => RunState::Activity(Activity::Explore),
+        PlayerCommand::GetItem => {
+            get_item(&mut gs.ecs);
+            RunState::PlayerTurn
+        }
+        PlayerCommand::CloseDoor => {
+            if close_door(&mut gs.ecs) {
+                RunState::PlayerTurn
+            } else {
+                RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::Descend => {
+            if try_descend(&mut gs.ecs) {
+                RunState::NextLevel
+            } else {
+                RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::UseItem { slot, target } => {
+            if use_backpack_slot(&mut gs.ecs, slot, target) {
+                RunState::PlayerTurn
+            } else {
+                RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::DropItem { slot } => {
+            if drop_backpack_slot(&mut gs.ecs, slot) {
+                RunState::PlayerTurn
+            } else {
+                RunState::AwaitingInput
             }
         }
-        _ => return RunState::AwaitingInput,
+        PlayerCommand::RunState(state) => state,
+        PlayerCommand::None => RunState::AwaitingInput,
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
+    // every key goes through the binding file, movement included
+    match ctx.key {
+        Some(key) => perform_command(gs, keybindings::get().command(key)),
+        _ => RunState::AwaitingInput,
     }
 }
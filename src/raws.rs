@@ -0,0 +1,397 @@
+use super::{map_builders, spawn_table::SpawnTable, EquipmentSlot};
+use rltk::RGB;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::sync::OnceLock;
+
+pub const RAWS_PATH: &str = "raws/spawns.json";
+
+static RAWS: OnceLock<Raws> = OnceLock::new();
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Raws {
+    pub monsters: Vec<MonsterRaw>,
+    pub items: Vec<ItemRaw>,
+    pub map_builders: Vec<MapBuilderRaw>,
+}
+
+impl Raws {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn monster_spawns(&self) -> Vec<(&str, &SpawnRaw)> {
+        self.monsters
+            .iter()
+            .map(|monster| (monster.name.as_str(), &monster.spawn))
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn item_spawns(&self) -> Vec<(&str, &SpawnRaw)> {
+        self.items
+            .iter()
+            .map(|item| (item.name.as_str(), &item.spawn))
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn map_builder_spawns(&self) -> Vec<(&str, &SpawnRaw)> {
+        self.map_builders
+            .iter()
+            .map(|builder| (builder.name.as_str(), &builder.spawn))
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn monster_table(&self) -> SpawnTable {
+        spawn_table(self.monster_spawns().into_iter())
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn item_table(&self) -> SpawnTable {
+        spawn_table(self.item_spawns().into_iter())
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn map_builder_table(&self) -> SpawnTable {
+        spawn_table(self.map_builder_spawns().into_iter())
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn monster(&self, name: &str) -> Option<&MonsterRaw> {
+        self.monsters.iter().find(|monster| monster.name == name)
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn item(&self, name: &str) -> Option<&ItemRaw> {
+        self.items.iter().find(|item| item.name == name)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RenderableRaw {
+    #[serde(deserialize_with = "glyph")]
+    pub glyph: rltk::FontCharType,
+    #[serde(deserialize_with = "colour")]
+    pub fg: RGB,
+    #[serde(deserialize_with = "colour")]
+    pub bg: RGB,
+    pub order: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StatsRaw {
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MonsterRaw {
+    pub name: String,
+    pub renderable: RenderableRaw,
+    pub stats: StatsRaw,
+    pub vision: i32,
+    pub xp: i32,
+    #[serde(default)]
+    pub spawn: SpawnRaw,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ItemEffectsRaw {
+    pub provides_healing: Option<i32>,
+    pub ranged: Option<i32>,
+    pub inflicts_damage: Option<i32>,
+    pub area_of_effect: Option<i32>,
+    pub confusion: Option<i32>,
+    pub sleep: Option<i32>,
+    pub poison: Option<OverTimeRaw>,
+    pub regeneration: Option<OverTimeRaw>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// an effect that does `amount` every turn for `turns` turns
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct OverTimeRaw {
+    pub turns: i32,
+    pub amount: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EquippableRaw {
+    pub slot: EquipmentSlot,
+    #[serde(default)]
+    pub power_bonus: i32,
+    #[serde(default)]
+    pub defense_bonus: i32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ItemRaw {
+    pub name: String,
+    pub renderable: RenderableRaw,
+    #[serde(default)]
+    pub consumable: bool,
+    #[serde(default)]
+    pub effects: ItemEffectsRaw,
+    pub equippable: Option<EquippableRaw>,
+    #[serde(default)]
+    pub spawn: SpawnRaw,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// which generator digs a level; the name has to be one of map_builders::BUILDER_NAMES
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MapBuilderRaw {
+    pub name: String,
+    #[serde(default)]
+    pub spawn: SpawnRaw,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// how often and on which levels an entry turns up
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields, default)]
+pub struct SpawnRaw {
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: Option<i32>,
+}
+
+impl Default for SpawnRaw {
+    fn default() -> SpawnRaw {
+        SpawnRaw {
+            weight: 1,
+            min_depth: 1,
+            max_depth: None,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// glyphs are written as a single character and stored as the code page 437 index
+fn glyph<'de, D: Deserializer<'de>>(deserializer: D) -> Result<rltk::FontCharType, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(rltk::to_cp437(c)),
+        _ => Err(serde::de::Error::custom(format!(
+            "expected a single character, found \"{}\"",
+            text
+        ))),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// colours are written as html hex codes, e.g. "#FF0000"
+fn colour<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RGB, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    RGB::from_hex(&text).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "expected a colour like \"#FF0000\", found \"{}\"",
+            text
+        ))
+    })
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Debug)]
+pub enum RawsError {
+    Io {
+        file: String,
+        error: std::io::Error,
+    },
+    Schema {
+        file: String,
+        field: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for RawsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RawsError::Io { file, error } => write!(f, "{}: {}", file, error),
+            RawsError::Schema {
+                file,
+                field,
+                message,
+            } => write!(f, "{}: field `{}`: {}", file, field, message),
+        }
+    }
+}
+
+impl std::error::Error for RawsError {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn spawn_table<'a>(entries: impl Iterator<Item = (&'a str, &'a SpawnRaw)>) -> SpawnTable {
+    entries.fold(SpawnTable::new(), |table, (name, spawn)| {
+        table.add(name, spawn.weight, spawn.min_depth, spawn.max_depth)
+    })
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn check_table(file: &str, table: &str, entries: &[(&str, &SpawnRaw)]) -> Result<(), RawsError> {
+    let schema_error = |field: String, message: String| RawsError::Schema {
+        file: file.to_string(),
+        field,
+        message,
+    };
+
+    for (i, (name, spawn)) in entries.iter().enumerate() {
+        if entries[..i].iter().any(|(other, _)| other == name) {
+            return Err(schema_error(
+                format!("{}[{}].name", table, i),
+                format!("duplicate name \"{}\"", name),
+            ));
+        }
+        if spawn.weight < 0 {
+            return Err(schema_error(
+                format!("{}[{}].spawn.weight", table, i),
+                "weight can't be negative".to_string(),
+            ));
+        }
+        if spawn
+            .max_depth
+            .is_some_and(|max_depth| max_depth < spawn.min_depth)
+        {
+            return Err(schema_error(
+                format!("{}[{}].spawn.max_depth", table, i),
+                "max_depth is shallower than min_depth".to_string(),
+            ));
+        }
+    }
+
+    // something has to be able to spawn on the first level
+    if spawn_table(entries.iter().copied())
+        .probabilities(1)
+        .is_empty()
+    {
+        return Err(schema_error(
+            table.to_string(),
+            "no entry can spawn on the first level".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn read(file: &str) -> Result<Raws, RawsError> {
+    let text = std::fs::read_to_string(file).map_err(|error| RawsError::Io {
+        file: file.to_string(),
+        error,
+    })?;
+
+    let de = &mut serde_json::Deserializer::from_str(&text);
+    let raws: Raws = serde_path_to_error::deserialize(de).map_err(|e| RawsError::Schema {
+        file: file.to_string(),
+        field: e.path().to_string(),
+        message: e.inner().to_string(),
+    })?;
+
+    check_table(file, "monsters", &raws.monster_spawns())?;
+    check_table(file, "items", &raws.item_spawns())?;
+    check_table(file, "map_builders", &raws.map_builder_spawns())?;
+    for (i, builder) in raws.map_builders.iter().enumerate() {
+        if !map_builders::BUILDER_NAMES.contains(&builder.name.as_str()) {
+            return Err(RawsError::Schema {
+                file: file.to_string(),
+                field: format!("map_builders[{}].name", i),
+                message: format!(
+                    "unknown map builder \"{}\", expected one of {}",
+                    builder.name,
+                    map_builders::BUILDER_NAMES.join(", ")
+                ),
+            });
+        }
+    }
+    Ok(raws)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// load at startup so a broken file is reported before any world is built
+pub fn load(file: &str) -> Result<(), RawsError> {
+    let raws = read(file)?;
+    let _ = RAWS.set(raws);
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn get() -> &'static Raws {
+    RAWS.get_or_init(|| read(RAWS_PATH).unwrap_or_else(|e| panic!("{}", e)))
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn spawn(weight: i32, min_depth: i32) -> SpawnRaw {
+        SpawnRaw {
+            weight,
+            min_depth,
+            max_depth: None,
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn error_field(entries: &[(&str, &SpawnRaw)]) -> Option<String> {
+        match check_table("test.json", "monsters", entries) {
+            Err(RawsError::Schema { field, .. }) => Some(field),
+            _ => None,
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn a_valid_table_passes() {
+        let (orc, ogre) = (spawn(3, 1), spawn(1, 4));
+        assert!(check_table("test.json", "monsters", &[("Orc", &orc), ("Ogre", &ogre)]).is_ok());
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let (first, second) = (spawn(1, 1), spawn(2, 1));
+        assert_eq!(
+            error_field(&[("Orc", &first), ("Orc", &second)]),
+            Some("monsters[1].name".to_string())
+        );
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn negative_weights_are_rejected() {
+        let (orc, goblin) = (spawn(1, 1), spawn(-1, 1));
+        assert_eq!(
+            error_field(&[("Orc", &orc), ("Goblin", &goblin)]),
+            Some("monsters[1].spawn.weight".to_string())
+        );
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn an_empty_first_level_is_rejected() {
+        let (orc, ogre) = (spawn(0, 1), spawn(1, 2));
+        assert_eq!(
+            error_field(&[("Orc", &orc), ("Ogre", &ogre)]),
+            Some("monsters".to_string())
+        );
+        assert_eq!(error_field(&[]), Some("monsters".to_string()));
+    }
+}
@@ -1,7 +1,8 @@
 use super::Position;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------------------------ //
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Rect {
     pub min: Position,
     pub max: Position,
@@ -60,9 +61,4 @@ impl Rect {
             y: self.min.y + (i / self.width()),
         }
     }
-
-    // ------------------------------------------------------------------------------------------------------------------ //
-    pub fn print_debug(&self) {
-        println!("rect: {:?} -> {:?}", self.min, self.max);
-    }
 }
@@ -0,0 +1,373 @@
+use super::headless::{parse_command, HeadlessDriver};
+use super::player::PlayerCommand;
+use super::{
+    CombatStats, Experience, GameLog, GameSeed, InBackpack, LogCategory, Map, Name, Position,
+    RunState,
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+pub const REPLAY_PATH: &str = "./replay.txt";
+
+// how long each replayed command stays on screen
+const REPLAY_STEP_MS: f32 = 100.0;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// fnv-1a, so a hash written by one build can be checked by another
+struct StateHasher(u64);
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// everything a command can change: the map and its doors, every creature and item, the backpacks and the generator.
+// entity ids aren't part of it, so a game that was saved and loaded still hashes the same
+pub fn state_hash(ecs: &World) -> u64 {
+    let mut hasher = StateHasher(0xcbf29ce484222325);
+
+    let map = ecs.fetch::<Map>();
+    map.depth.hash(&mut hasher);
+    for tile in map.tiles.iter() {
+        (*tile as u8).hash(&mut hasher);
+    }
+    map.closed_doors.hash(&mut hasher);
+
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let experience = ecs.read_storage::<Experience>();
+
+    let name_of = |entity: Entity| names.get(entity).map_or("", |name| name.name.as_str());
+    let mut things: Vec<_> = (&entities, &positions, combat_stats.maybe())
+        .join()
+        .map(|(entity, pos, stats)| {
+            (
+                pos.x,
+                pos.y,
+                name_of(entity),
+                stats.map(|s| (s.hp, s.max_hp, s.defense, s.power)),
+                experience.get(entity).map(|e| (e.level, e.xp)),
+            )
+        })
+        .collect();
+    things.sort();
+    things.hash(&mut hasher);
+
+    let mut carried: Vec<_> = (&entities, &backpack)
+        .join()
+        .map(|(item, pack)| (name_of(pack.owner), name_of(item)))
+        .collect();
+    carried.sort();
+    carried.hash(&mut hasher);
+
+    // the next roll stands in for the generator's state
+    let mut rng = (*ecs.fetch::<RandomNumberGenerator>()).clone();
+    rng.next_u64().hash(&mut hasher);
+
+    hasher.finish()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the replay file form of a command, in the same words as a --commands file;
+// commands that only open a menu or touch the save file aren't part of a replay
+pub fn command_text(command: &PlayerCommand) -> Option<String> {
+    let text = match command {
+        PlayerCommand::Move(-1, 0) => "left".to_string(),
+        PlayerCommand::Move(1, 0) => "right".to_string(),
+        PlayerCommand::Move(0, -1) => "up".to_string(),
+        PlayerCommand::Move(0, 1) => "down".to_string(),
+        PlayerCommand::Move(-1, -1) => "up-left".to_string(),
+        PlayerCommand::Move(1, -1) => "up-right".to_string(),
+        PlayerCommand::Move(-1, 1) => "down-left".to_string(),
+        PlayerCommand::Move(1, 1) => "down-right".to_string(),
+        PlayerCommand::Move(_, _) => return None,
+        PlayerCommand::GetItem => "get".to_string(),
+        PlayerCommand::Descend => "descend".to_string(),
+        PlayerCommand::Wait => "wait".to_string(),
+        PlayerCommand::Rest => "rest".to_string(),
+        PlayerCommand::Explore => "explore".to_string(),
+        PlayerCommand::CloseDoor => "close".to_string(),
+        PlayerCommand::UseItem { slot, target } => match target {
+            None => format!("use {}", slot),
+            Some(target) => format!("use {} {} {}", slot, target.x, target.y),
+        },
+        PlayerCommand::DropItem { slot } => format!("drop {}", slot),
+        PlayerCommand::RunState(_) | PlayerCommand::None => return None,
+    };
+    Some(text)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// writes each command to the replay file once the turn it started has played out
+pub struct Recorder {
+    file: File,
+    pending: Option<String>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// plays a recording back in the window, checking each step against the recorded hash
+pub struct Replay {
+    steps: Vec<(PlayerCommand, u64)>,
+    next: usize,
+    pending: Option<usize>,
+    drifted: bool,
+    timer: f32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// a new game starts a new file; a loaded game has no recorder, as it can't be rebuilt from the seed
+pub fn start_recording(ecs: &mut World, path: &str) {
+    let seed = ecs.fetch::<GameSeed>().seed;
+    let file = File::create(path).and_then(|mut file| {
+        writeln!(file, "seed {}", seed)?;
+        Ok(file)
+    });
+    match file {
+        Ok(file) => ecs.insert(Recorder {
+            file,
+            pending: None,
+        }),
+        Err(e) => ecs.fetch_mut::<GameLog>().add(
+            LogCategory::System,
+            format!("Unable to record a replay: {}", e),
+        ),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn note_command(ecs: &mut World, command: &PlayerCommand) {
+    if let Some(mut recorder) = ecs.try_fetch_mut::<Recorder>() {
+        recorder.pending = command_text(command);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// called once the game is waiting for input again, when the last command's turn has finished
+pub fn finish_step(ecs: &mut World) {
+    let recorder_pending = ecs
+        .try_fetch_mut::<Recorder>()
+        .and_then(|mut recorder| recorder.pending.take());
+    let replay_pending = ecs
+        .try_fetch_mut::<Replay>()
+        .and_then(|mut replay| replay.pending.take());
+    if recorder_pending.is_none() && replay_pending.is_none() {
+        return;
+    }
+    let hash = state_hash(ecs);
+
+    if let Some(text) = recorder_pending {
+        let written = match ecs.try_fetch_mut::<Recorder>() {
+            Some(mut recorder) => writeln!(recorder.file, "{} # {:016x}", text, hash),
+            None => Ok(()),
+        };
+        if let Err(e) = written {
+            ecs.remove::<Recorder>();
+            ecs.fetch_mut::<GameLog>()
+                .add(LogCategory::System, format!("Recording stopped: {}", e));
+        }
+    }
+
+    if let Some(step) = replay_pending {
+        let mut message = None;
+        if let Some(mut replay) = ecs.try_fetch_mut::<Replay>() {
+            if !replay.drifted && replay.steps[step].1 != hash {
+                replay.drifted = true;
+                message = Some(format!(
+                    "Replay drifted from the recording at turn {}.",
+                    step + 1
+                ));
+            } else if replay.next == replay.steps.len() {
+                message = Some("Replay finished.".to_string());
+            }
+        }
+        if let Some(message) = message {
+            ecs.fetch_mut::<GameLog>().add(LogCategory::System, message);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// the next replayed command, once the last one has been on screen long enough
+pub fn next_command(ecs: &mut World, frame_time_ms: f32) -> Option<PlayerCommand> {
+    let mut replay = ecs.try_fetch_mut::<Replay>()?;
+    if replay.next >= replay.steps.len() {
+        return None;
+    }
+    replay.timer += frame_time_ms;
+    if replay.timer < REPLAY_STEP_MS {
+        return Some(PlayerCommand::None);
+    }
+    replay.timer = 0.0;
+
+    let step = replay.next;
+    replay.next += 1;
+    replay.pending = Some(step);
+    Some(replay.steps[step].0)
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub struct Recording {
+    pub seed: u64,
+    pub steps: Vec<(PlayerCommand, u64)>,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// "seed <n>" on the first line, then one "<command> # <hash>" per line
+pub fn read_recording(path: &str) -> Result<Recording, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut lines = text.lines().enumerate();
+
+    let seed = lines
+        .next()
+        .and_then(|(_, line)| line.strip_prefix("seed "))
+        .and_then(|seed| seed.trim().parse().ok())
+        .ok_or_else(|| format!("{}:1: expected 'seed <n>'", path))?;
+
+    let mut steps = Vec::new();
+    for (line_number, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = || format!("{}:{}: bad replay step '{}'", path, line_number + 1, line);
+        let (command, hash) = line.split_once('#').ok_or_else(error)?;
+        let command = parse_command(command).ok_or_else(error)?;
+        let hash = u64::from_str_radix(hash.trim(), 16).map_err(|_| error())?;
+        steps.push((command, hash));
+    }
+    Ok(Recording { seed, steps })
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn start_replay(ecs: &mut World, recording: Recording) {
+    ecs.insert(Replay {
+        steps: recording.steps,
+        next: 0,
+        pending: None,
+        drifted: false,
+        timer: 0.0,
+    });
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// runs a recording without a window; fails if the game no longer plays out the same way
+pub fn run_headless(path: &str) -> rltk::BError {
+    let recording = read_recording(path)?;
+    let mut driver = HeadlessDriver::new(recording.seed);
+    println!(
+        "replay {}: seed {}, {} turns",
+        path,
+        recording.seed,
+        recording.steps.len()
+    );
+
+    let mut drift = None;
+    for (i, (command, expected)) in recording.steps.iter().enumerate() {
+        if *driver.state.ecs.fetch::<RunState>() == RunState::GameOver {
+            break;
+        }
+        driver.step(*command);
+        let hash = state_hash(&driver.state.ecs);
+        if drift.is_none() && hash != *expected {
+            drift = Some((i + 1, *command, *expected, hash));
+        }
+    }
+
+    println!("final state hash {:016x}", state_hash(&driver.state.ecs));
+    match drift {
+        None => {
+            println!("no drift");
+            Ok(())
+        }
+        Some((turn, command, expected, found)) => Err(format!(
+            "first drift at turn {} ({}): expected {:016x}, found {:016x}",
+            turn,
+            command_text(&command).unwrap_or_default(),
+            expected,
+            found
+        )
+        .into()),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn a_recording_replays_without_drift() {
+        let path = std::env::temp_dir().join(format!("replay-test-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let commands = [
+            "right",
+            "right",
+            "down",
+            "down",
+            "get",
+            "left",
+            "up",
+            "up",
+            "left",
+            "down",
+            "right",
+            "right",
+            "right",
+            "up",
+            "get",
+            "down",
+            "down",
+            "left",
+            "explore",
+            "rest",
+            "up-left",
+            "down-right",
+            "wait",
+            "close",
+            "explore",
+            "down-left",
+            "up-right",
+            "explore",
+        ];
+
+        let mut driver = HeadlessDriver::new(7);
+        start_recording(&mut driver.state.ecs, path);
+        for command in commands.iter() {
+            driver.step(parse_command(command).unwrap());
+        }
+        let recorded_hash = state_hash(&driver.state.ecs);
+        driver.state.ecs.remove::<Recorder>();
+
+        let recording = read_recording(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(recording.seed, 7);
+        assert_eq!(recording.steps.len(), commands.len());
+
+        let mut replayed = HeadlessDriver::new(recording.seed);
+        for (turn, (command, expected)) in recording.steps.iter().enumerate() {
+            replayed.step(*command);
+            assert_eq!(
+                state_hash(&replayed.state.ecs),
+                *expected,
+                "drift at turn {}",
+                turn + 1
+            );
+        }
+        assert_eq!(state_hash(&replayed.state.ecs), recorded_hash);
+    }
+}
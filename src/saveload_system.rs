@@ -0,0 +1,290 @@
+use super::components::*;
+use super::{register_components, GameLog, Map};
+use rltk::{Point, RandomNumberGenerator};
+use serde::{Deserialize, Serialize};
+use specs::error::NoError;
+use specs::prelude::*;
+use specs::saveload::{
+    DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
+};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+pub const SAVE_PATH: &str = "./savegame.json";
+
+// bump this whenever a saved component or resource changes shape
+pub const SAVE_VERSION: u32 = 10;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    Version { found: u32, expected: u32 },
+    NoPlayer,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "unable to access save file: {}", e),
+            SaveError::Format(e) => write!(f, "save file is corrupt: {}", e),
+            SaveError::Version { found, expected } => write!(
+                f,
+                "save file version {} is not supported (expected version {})",
+                found, expected
+            ),
+            SaveError::NoPlayer => write!(f, "save file does not contain a player"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Format(e)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+macro_rules! serialize_individually {
+    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
+        $(
+        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+            &( $ecs.read_storage::<$type>(), ),
+            &$data.0,
+            &$data.1,
+            &mut $ser,
+        )?;
+        )*
+    };
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+macro_rules! deserialize_individually {
+    ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
+        $(
+        DeserializeComponents::<NoError, _>::deserialize(
+            &mut ( &mut $ecs.write_storage::<$type>(), ),
+            &$data.0,
+            &mut $data.1,
+            &mut $data.2,
+            &mut $de,
+        )?;
+        )*
+    };
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn save_exists() -> bool {
+    std::path::Path::new(SAVE_PATH).exists()
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn save_game(ecs: &mut World) -> Result<(), SaveError> {
+    // stash the resources on a temporary entity so they go out with the components
+    let map = (*ecs.fetch::<Map>()).clone();
+    let log = (*ecs.fetch::<GameLog>()).clone();
+    let rng = (*ecs.fetch::<RandomNumberGenerator>()).clone();
+    let seed = *ecs.fetch::<GameSeed>();
+    let helper = ecs
+        .create_entity()
+        .with(SerializationHelper {
+            map,
+            log,
+            rng,
+            seed,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    let result = write_save(ecs);
+
+    ecs.delete_entity(helper)
+        .expect("Unable to delete save helper");
+    result
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn write_save(ecs: &World) -> Result<(), SaveError> {
+    let writer = BufWriter::new(File::create(SAVE_PATH)?);
+    let mut serializer = serde_json::Serializer::new(writer);
+    SaveHeader {
+        version: SAVE_VERSION,
+    }
+    .serialize(&mut serializer)?;
+
+    let data = (
+        ecs.entities(),
+        ecs.read_storage::<SimpleMarker<SerializeMe>>(),
+    );
+    serialize_individually!(
+        ecs,
+        serializer,
+        data,
+        Position,
+        CombatStats,
+        Renderable,
+        Viewshed,
+        Name,
+        BlocksTile,
+        Player,
+        Monster,
+        WantsToMelee,
+        Experience,
+        GrantsXp,
+        PlayerRecord,
+        Item,
+        Consumable,
+        ProvidesHealing,
+        InBackpack,
+        WantsToPickupItem,
+        WantsToUseItem,
+        WantsToDropItem,
+        Ranged,
+        InflictsDamage,
+        AreaOfEffect,
+        Confusion,
+        Sleep,
+        Poison,
+        Regeneration,
+        StatusEffects,
+        Equippable,
+        Equipped,
+        MeleePowerBonus,
+        DefenseBonus,
+        WantsToRemoveItem,
+        Door,
+        WantsToOpenDoor,
+        WantsToCloseDoor,
+        SerializationHelper
+    );
+
+    serializer.into_inner().flush()?;
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn load_game() -> Result<World, SaveError> {
+    let data = std::fs::read_to_string(SAVE_PATH)?;
+    let mut de = serde_json::Deserializer::from_str(&data);
+
+    // refuse anything written by a different version before touching any components
+    let header = SaveHeader::deserialize(&mut de)?;
+    if header.version != SAVE_VERSION {
+        return Err(SaveError::Version {
+            found: header.version,
+            expected: SAVE_VERSION,
+        });
+    }
+
+    // load into a fresh world so a bad file leaves the running game untouched
+    let mut ecs = World::new();
+    register_components(&mut ecs);
+    ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+
+    {
+        let mut d = (
+            &mut ecs.entities(),
+            &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
+            &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
+        );
+        deserialize_individually!(
+            ecs,
+            de,
+            d,
+            Position,
+            CombatStats,
+            Renderable,
+            Viewshed,
+            Name,
+            BlocksTile,
+            Player,
+            Monster,
+            WantsToMelee,
+            Experience,
+            GrantsXp,
+            PlayerRecord,
+            Item,
+            Consumable,
+            ProvidesHealing,
+            InBackpack,
+            WantsToPickupItem,
+            WantsToUseItem,
+            WantsToDropItem,
+            Ranged,
+            InflictsDamage,
+            AreaOfEffect,
+            Confusion,
+            Sleep,
+            Poison,
+            Regeneration,
+            StatusEffects,
+            Equippable,
+            Equipped,
+            MeleePowerBonus,
+            DefenseBonus,
+            WantsToRemoveItem,
+            Door,
+            WantsToOpenDoor,
+            WantsToCloseDoor,
+            SerializationHelper
+        );
+    }
+    de.end()?;
+
+    // restore the resources from the helper entity
+    let helper = {
+        let entities = ecs.entities();
+        let helpers = ecs.read_storage::<SerializationHelper>();
+        (&entities, &helpers)
+            .join()
+            .map(|(entity, helper)| (entity, helper.clone()))
+            .next()
+    };
+    let (helper_entity, helper) = helper.ok_or_else(|| {
+        SaveError::Format(serde::de::Error::custom(
+            "save file has no map, log, generator or seed",
+        ))
+    })?;
+    let mut map = helper.map;
+    map.tile_content = vec![Vec::new(); map.size.area() as usize];
+    ecs.insert(map);
+    ecs.insert(helper.log);
+    ecs.insert(helper.rng);
+    ecs.insert(helper.seed);
+    ecs.delete_entity(helper_entity)
+        .expect("Unable to delete save helper");
+
+    // the player entity and position resources are rebuilt from the player's components
+    let mut player = None;
+    {
+        let entities = ecs.entities();
+        let players = ecs.read_storage::<Player>();
+        let positions = ecs.read_storage::<Position>();
+        for (entity, _player, pos) in (&entities, &players, &positions).join() {
+            player = Some((entity, Point::new(pos.x, pos.y)));
+        }
+    }
+    let (player_entity, player_pos) = player.ok_or(SaveError::NoPlayer)?;
+    ecs.insert(player_entity);
+    ecs.insert(player_pos);
+    ecs.insert(RunState::PreRun);
+    ecs.maintain();
+
+    Ok(ecs)
+}
@@ -0,0 +1,150 @@
+use rltk::RandomNumberGenerator;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub struct SpawnEntry {
+    pub name: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: Option<i32>,
+}
+
+impl SpawnEntry {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn allowed_at(&self, depth: i32) -> bool {
+        self.weight > 0
+            && depth >= self.min_depth
+            && self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[derive(Default)]
+pub struct SpawnTable {
+    entries: Vec<SpawnEntry>,
+}
+
+impl SpawnTable {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn new() -> SpawnTable {
+        SpawnTable {
+            entries: Vec::new(),
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn add<S: ToString>(
+        mut self,
+        name: S,
+        weight: i32,
+        min_depth: i32,
+        max_depth: Option<i32>,
+    ) -> SpawnTable {
+        self.entries.push(SpawnEntry {
+            name: name.to_string(),
+            weight,
+            min_depth,
+            max_depth,
+        });
+        self
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn total_weight(&self, depth: i32) -> i32 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.allowed_at(depth))
+            .map(|entry| entry.weight)
+            .sum()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn roll(&self, rng: &mut RandomNumberGenerator, depth: i32) -> Option<&str> {
+        let total = self.total_weight(depth);
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rng.roll_dice(1, total) - 1;
+        for entry in self.entries.iter().filter(|entry| entry.allowed_at(depth)) {
+            if roll < entry.weight {
+                return Some(&entry.name);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // the chance of each entry being picked at a depth, for checking balance
+    pub fn probabilities(&self, depth: i32) -> Vec<(&str, f32)> {
+        let total = self.total_weight(depth);
+        self.entries
+            .iter()
+            .filter(|entry| entry.allowed_at(depth))
+            .map(|entry| (entry.name.as_str(), entry.weight as f32 / total as f32))
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLS: i32 = 1000;
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn rolled(table: &SpawnTable, depth: i32) -> Vec<String> {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        (0..ROLLS)
+            .filter_map(|_| table.roll(&mut rng, depth).map(|name| name.to_string()))
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn weight_zero_is_never_rolled() {
+        let table = SpawnTable::new()
+            .add("never", 0, 1, None)
+            .add("always", 1, 1, None);
+        let names = rolled(&table, 1);
+        assert_eq!(names.len(), ROLLS as usize);
+        assert!(names.iter().all(|name| name == "always"));
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn depth_limits_filter_entries() {
+        let table = SpawnTable::new()
+            .add("shallow", 1, 1, Some(2))
+            .add("deep", 1, 3, None);
+        assert!(rolled(&table, 1).iter().all(|name| name == "shallow"));
+        assert!(rolled(&table, 2).iter().all(|name| name == "shallow"));
+        assert!(rolled(&table, 3).iter().all(|name| name == "deep"));
+        assert!(SpawnTable::new()
+            .add("deep", 1, 3, None)
+            .roll(&mut RandomNumberGenerator::seeded(1), 1)
+            .is_none());
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    #[test]
+    fn probabilities_sum_to_one() {
+        let table = SpawnTable::new()
+            .add("a", 3, 1, None)
+            .add("b", 5, 1, Some(4))
+            .add("c", 0, 1, None)
+            .add("d", 7, 2, None);
+        for depth in 1..=6 {
+            let probabilities = table.probabilities(depth);
+            let total: f32 = probabilities.iter().map(|(_name, chance)| chance).sum();
+            assert!(
+                (total - 1.0).abs() < 1e-5,
+                "depth {} sums to {}",
+                depth,
+                total
+            );
+            assert!(probabilities.iter().all(|(name, _chance)| *name != "c"));
+        }
+    }
+}
@@ -1,11 +1,14 @@
 use super::{
-    BlocksTile, CombatStats, Consumable, InflictsDamage, Item, Monster, Name, Player, Position,
-    ProvidesHealing, Ranged, Rect, Renderable, Viewshed,
+    raws, spawn_table::SpawnTable, AreaOfEffect, BlocksTile, CombatStats, Confusion, Consumable,
+    DefenseBonus, Door, Equippable, Experience, GrantsXp, InflictsDamage, Item, MeleePowerBonus,
+    Monster, Name, Player, PlayerRecord, Poison, Position, ProvidesHealing, Ranged, Regeneration,
+    Renderable, SerializeMe, Sleep, Viewshed,
 };
 use rand::seq::SliceRandom;
 use rand_core::{impls, Error, RngCore};
 use rltk::{RandomNumberGenerator, RGB};
 use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
 
 const MAX_MONSTERS_PER_ROOM: i32 = 2;
 const MAX_ITEMS_PER_ROOM: i32 = 4;
@@ -15,6 +18,7 @@ pub struct SpawnContext<'a> {
     pub world: &'a mut World,
     pub rng: &'a mut RandomNumberGenerator,
     pub position: Position,
+    pub depth: i32,
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
@@ -39,113 +43,169 @@ pub fn player(context: &mut SpawnContext) -> Entity {
         .with(Name {
             name: "Player".to_string(),
         })
+        .with(Experience { level: 1, xp: 0 })
+        .with(PlayerRecord { turns: 0, kills: 0 })
         .with(Viewshed {
             visible_tiles: Vec::new(),
             range: 8,
             dirty: true,
         })
+        .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-pub fn random_monster(context: &mut SpawnContext) -> Entity {
-    match context.rng.roll_dice(1, 2) {
-        1 => orc(&mut context.world, &context.position),
-        _ => goblin(&mut context.world, &context.position),
-    }
-}
+pub const DOOR_CLOSED_GLYPH: char = '+';
+// not '/', which is already the dagger
+pub const DOOR_OPEN_GLYPH: char = '\'';
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn orc(world: &mut World, position: &Position) -> Entity {
-    monster(world, position, rltk::to_cp437('o'), "Orc")
+// doors start closed, and are drawn under anything that stands in the doorway
+pub fn door(world: &mut World, position: Position) -> Entity {
+    world
+        .create_entity()
+        .with(position)
+        .with(Renderable {
+            glyph: rltk::to_cp437(DOOR_CLOSED_GLYPH),
+            fg: RGB::named(rltk::CHOCOLATE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 3,
+        })
+        .with(Name {
+            name: "Door".to_string(),
+        })
+        .with(Door { open: false })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn goblin(world: &mut World, position: &Position) -> Entity {
-    monster(world, position, rltk::to_cp437('g'), "Goblin")
+fn renderable(raw: &raws::RenderableRaw) -> Renderable {
+    Renderable {
+        glyph: raw.glyph,
+        fg: raw.fg,
+        bg: raw.bg,
+        render_order: raw.order,
+    }
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn monster<S: ToString>(
-    world: &mut World,
-    position: &Position,
-    glyph: rltk::FontCharType,
-    name: S,
-) -> Entity {
+fn monster(world: &mut World, position: &Position, raw: &raws::MonsterRaw) -> Entity {
     world
         .create_entity()
         .with(*position)
-        .with(Renderable {
-            glyph,
-            fg: RGB::named(rltk::RED),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 0,
-        })
+        .with(renderable(&raw.renderable))
         .with(Viewshed {
             visible_tiles: Vec::new(),
-            range: 8,
+            range: raw.vision,
             dirty: true,
         })
         .with(Monster {})
         .with(Name {
-            name: name.to_string(),
+            name: raw.name.clone(),
         })
         .with(BlocksTile {})
         .with(CombatStats {
-            max_hp: 16,
-            hp: 16,
-            defense: 1,
-            power: 4,
+            max_hp: raw.stats.max_hp,
+            hp: raw.stats.max_hp,
+            defense: raw.stats.defense,
+            power: raw.stats.power,
         })
+        .with(GrantsXp { amount: raw.xp })
+        .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn health_potion(world: &mut World, position: &Position) -> Entity {
-    world
+fn item(world: &mut World, position: &Position, raw: &raws::ItemRaw) -> Entity {
+    let mut builder = world
         .create_entity()
         .with(*position)
-        .with(Renderable {
-            glyph: rltk::to_cp437('i'),
-            fg: RGB::named(rltk::MAGENTA),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
+        .with(renderable(&raw.renderable))
         .with(Name {
-            name: "Health Potion".to_string(),
+            name: raw.name.clone(),
         })
-        .with(Item {})
-        .with(Consumable {})
-        .with(ProvidesHealing { amount: 8 })
-        .build()
+        .with(Item {});
+
+    if raw.consumable {
+        builder = builder.with(Consumable {});
+    }
+    let effects = &raw.effects;
+    if let Some(amount) = effects.provides_healing {
+        builder = builder.with(ProvidesHealing { amount });
+    }
+    if let Some(range) = effects.ranged {
+        builder = builder.with(Ranged { range });
+    }
+    if let Some(amount) = effects.inflicts_damage {
+        builder = builder.with(InflictsDamage { amount });
+    }
+    if let Some(radius) = effects.area_of_effect {
+        builder = builder.with(AreaOfEffect { radius });
+    }
+    if let Some(turns) = effects.confusion {
+        builder = builder.with(Confusion { turns });
+    }
+    if let Some(turns) = effects.sleep {
+        builder = builder.with(Sleep { turns });
+    }
+    if let Some(poison) = &effects.poison {
+        builder = builder.with(Poison {
+            turns: poison.turns,
+            damage: poison.amount,
+        });
+    }
+    if let Some(regeneration) = &effects.regeneration {
+        builder = builder.with(Regeneration {
+            turns: regeneration.turns,
+            amount: regeneration.amount,
+        });
+    }
+    if let Some(equip) = &raw.equippable {
+        builder = builder.with(Equippable { slot: equip.slot });
+        if equip.power_bonus != 0 {
+            builder = builder.with(MeleePowerBonus {
+                power: equip.power_bonus,
+            });
+        }
+        if equip.defense_bonus != 0 {
+            builder = builder.with(DefenseBonus {
+                defense: equip.defense_bonus,
+            });
+        }
+    }
+
+    builder.marked::<SimpleMarker<SerializeMe>>().build()
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn magic_missile_scroll(world: &mut World, position: &Position) -> Entity {
-    world
-        .create_entity()
-        .with(*position)
-        .with(Renderable {
-            glyph: rltk::to_cp437('}'),
-            fg: RGB::named(rltk::CYAN),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name {
-            name: "Magic Missile Scroll".to_string(),
-        })
-        .with(Item {})
-        .with(Consumable {})
-        .with(Ranged { range: 6 })
-        .with(InflictsDamage { amount: 8 })
-        .build()
+pub fn random_monster(context: &mut SpawnContext, table: &SpawnTable) -> Option<Entity> {
+    let name = table.roll(context.rng, context.depth)?;
+    let raw = raws::get().monster(name)?;
+    Some(monster(context.world, &context.position, raw))
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+fn random_item(context: &mut SpawnContext, table: &SpawnTable) -> Option<Entity> {
+    let name = table.roll(context.rng, context.depth)?;
+    let raw = raws::get().item(name)?;
+    Some(item(context.world, &context.position, raw))
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-fn random_item(context: &mut SpawnContext) -> Entity {
-    match context.rng.roll_dice(1, 2) {
-        1 => health_potion(&mut context.world, &context.position),
-        _ => magic_missile_scroll(&mut context.world, &context.position),
+pub fn print_spawn_odds(depth: i32) {
+    let raws = raws::get();
+    for (title, table) in [
+        ("monsters", raws.monster_table()),
+        ("items", raws.item_table()),
+        ("map builders", raws.map_builder_table()),
+    ]
+    .iter()
+    {
+        println!("{} at depth {}:", title, depth);
+        for (name, chance) in table.probabilities(depth) {
+            println!("  {:>6.2}%  {}", chance * 100.0, name);
+        }
     }
 }
 
@@ -166,40 +226,54 @@ impl RngCore for RngWrapper<'_> {
         impls::fill_bytes_via_next(self, dest)
     }
     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
 // ------------------------------------------------------------------------------------------------------------------ //
-pub fn spawn_room(world: &mut World, rng: &mut RandomNumberGenerator, room: &Rect) {
-    let mut room_positions = (0..room.area()).collect::<Vec<_>>();
-    room_positions.shuffle(&mut RngWrapper { rng });
+pub fn spawn_region(
+    world: &mut World,
+    rng: &mut RandomNumberGenerator,
+    region: &[Position],
+    depth: i32,
+) {
+    let mut region_positions = region.to_vec();
+    region_positions.shuffle(&mut RngWrapper { rng });
 
     let spawn_items = |count: i32| -> Vec<Position> {
-        let mut points: Vec<Position> = Vec::new();
-        let point_count = i32::min(count, room.area());
-        for ii in 0..point_count {
-            points.push(room.idx_position(room_positions[ii as usize] as usize));
-        }
-        points
+        let point_count = usize::min(i32::max(count, 0) as usize, region_positions.len());
+        region_positions[..point_count].to_vec()
     };
 
-    let monster_points = spawn_items(rng.range(0, MAX_MONSTERS_PER_ROOM));
+    // one more possible monster per region for every level below the first
+    let monster_table = raws::get().monster_table();
+    let item_table = raws::get().item_table();
+
+    let monster_points = spawn_items(rng.range(0, MAX_MONSTERS_PER_ROOM + depth - 1));
     let item_points = spawn_items(rng.range(0, MAX_ITEMS_PER_ROOM));
 
     for p in monster_points.iter() {
-        random_monster(&mut SpawnContext {
-            world: world,
-            rng: rng,
-            position: *p,
-        });
+        random_monster(
+            &mut SpawnContext {
+                world,
+                rng,
+                position: *p,
+                depth,
+            },
+            &monster_table,
+        );
     }
 
     for p in item_points.iter() {
-        random_item(&mut SpawnContext {
-            world: world,
-            rng: rng,
-            position: *p,
-        });
+        random_item(
+            &mut SpawnContext {
+                world,
+                rng,
+                position: *p,
+                depth,
+            },
+            &item_table,
+        );
     }
 }
@@ -0,0 +1,120 @@
+use super::{
+    gamelog::{GameLog, LogCategory},
+    CombatStats, Map, Name, Position, RunState, StatusEffects, StatusKind, SufferDamage,
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// ticks every lasting effect down once per turn, at the end of the monsters' move
+pub struct StatusEffectSystem {}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+impl<'a> System<'a> for StatusEffectSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
+        Entities<'a>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            runstate,
+            mut gamelog,
+            map,
+            entities,
+            mut status_effects,
+            mut combat_stats,
+            mut suffer_damage,
+            names,
+            positions,
+        ) = data;
+
+        if *runstate != RunState::MonsterTurn {
+            return;
+        }
+
+        let mut recovered: Vec<Entity> = Vec::new();
+        for (entity, statuses) in (&entities, &mut status_effects).join() {
+            for effect in statuses.effects.iter_mut() {
+                match effect.kind {
+                    StatusKind::Poison => SufferDamage::new_damage(
+                        &mut suffer_damage,
+                        entity,
+                        effect.potency,
+                        effect.source,
+                    ),
+                    StatusKind::Regeneration => {
+                        if let Some(stats) = combat_stats.get_mut(entity) {
+                            stats.hp = i32::min(stats.max_hp, stats.hp + effect.potency);
+                        }
+                    }
+                    StatusKind::Confusion | StatusKind::Sleep => {}
+                }
+                effect.turns -= 1;
+
+                if effect.turns <= 0 {
+                    let seen = positions
+                        .get(entity)
+                        .is_some_and(|pos| map.visible_tiles[map.position_idx(*pos)]);
+                    if entity == *player_entity {
+                        gamelog.add(
+                            LogCategory::Combat,
+                            format!("You are no longer {}.", effect.kind.label()),
+                        );
+                    } else if seen {
+                        gamelog.add(
+                            LogCategory::Combat,
+                            format!(
+                                "{} is no longer {}.",
+                                names.get(entity).unwrap().name,
+                                effect.kind.label()
+                            ),
+                        );
+                    }
+                }
+            }
+
+            statuses.effects.retain(|effect| effect.turns > 0);
+            if statuses.effects.is_empty() {
+                recovered.push(entity);
+            }
+        }
+
+        for entity in recovered {
+            status_effects.remove(entity);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+pub fn has_status(world: &World, entity: Entity, kind: StatusKind) -> bool {
+    world
+        .read_storage::<StatusEffects>()
+        .get(entity)
+        .is_some_and(|statuses| statuses.has(kind))
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// where a confused creature ends up going: any of the eight ways it could have walked
+pub fn random_direction(rng: &mut RandomNumberGenerator) -> (i32, i32) {
+    match rng.roll_dice(1, 8) {
+        1 => (-1, 0),
+        2 => (1, 0),
+        3 => (0, -1),
+        4 => (0, 1),
+        5 => (-1, -1),
+        6 => (1, -1),
+        7 => (-1, 1),
+        _ => (1, 1),
+    }
+}
@@ -1,7 +1,67 @@
 //mod viewport;
+use super::Map;
+use rltk::Point;
+use specs::prelude::*;
 
 pub struct Viewport {
     pub map_width: i32,
     pub map_height: i32,
     pub log_height: i32
-}
\ No newline at end of file
+}
+
+impl Default for Viewport {
+    fn default() -> Viewport {
+        Viewport {
+            map_width: 80,
+            map_height: 43,
+            log_height: 7
+        }
+    }
+}
+
+impl Viewport {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    // the part of the map on screen this frame: centred on the player, but never past the map's edges
+    pub fn camera(&self, ecs: &World) -> Camera {
+        let map = ecs.fetch::<Map>();
+        let focus = *ecs.fetch::<Point>();
+        let clamp = |centre: i32, view: i32, size: i32| {
+            i32::max(0, i32::min(centre - view / 2, size - view))
+        };
+        Camera {
+            origin: Point::new(
+                clamp(focus.x, self.map_width, map.size.width()),
+                clamp(focus.y, self.map_height, map.size.height()),
+            ),
+            width: self.map_width,
+            height: self.map_height,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------------------------ //
+// converts between map tiles and screen cells; the map area always starts at the top left of the screen
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub origin: Point,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Camera {
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn to_screen(self, world: Point) -> Option<Point> {
+        let screen = world - self.origin;
+        self.on_screen(screen).then_some(screen)
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    pub fn to_world(self, screen: Point) -> Option<Point> {
+        self.on_screen(screen).then_some(screen + self.origin)
+    }
+
+    // ------------------------------------------------------------------------------------------------------------------ //
+    fn on_screen(self, screen: Point) -> bool {
+        screen.x >= 0 && screen.x < self.width && screen.y >= 0 && screen.y < self.height
+    }
+}